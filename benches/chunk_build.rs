@@ -0,0 +1,98 @@
+use bevy::{prelude::*, sprite::TextureAtlas};
+use bevy_github_ci_template::{
+    autotile::AutotileRules,
+    coordinates::{ChunkPos, WorldPos},
+    fixtures::test_world,
+    game_world::GameWorld,
+    map::{get_around_blocks, tiles_atlas_layout, Block, SolidBlock},
+    tilemap::{block_color, build_chunk_meshes, get_tile},
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+//Chunk build time with one sprite entity per block, the way chunks used to be spawned,
+//against the batched meshes
+fn chunk_build(c: &mut Criterion) {
+    let game_world = test_world();
+    let atlas_layout = tiles_atlas_layout();
+    let rules = AutotileRules::load();
+    let chunk_index = ChunkPos::from_slot(
+        WorldPos::ZERO.chunk_slot(&game_world.config),
+        &game_world.config,
+    );
+    let mut group = c.benchmark_group("chunk_build");
+
+    group.bench_function("sprite_per_block", |b| {
+        b.iter(|| {
+            let mut world = World::new();
+            spawn_sprite_per_block(chunk_index, &game_world, &rules, &mut world);
+            world
+        })
+    });
+
+    group.bench_function("batched_mesh", |b| {
+        b.iter(|| {
+            let mut world = World::new();
+            let mut meshes = Assets::<Mesh>::default();
+            let chunk_meshes = build_chunk_meshes(chunk_index, &game_world, &atlas_layout, &rules);
+            for mesh in [
+                chunk_meshes.standard,
+                chunk_meshes.white,
+                chunk_meshes.colors,
+                chunk_meshes.standard_props,
+                chunk_meshes.white_props,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                world
+                    .spawn(SpatialBundle::default())
+                    .insert(meshes.add(mesh));
+            }
+            (world, meshes)
+        })
+    });
+
+    group.finish();
+}
+
+fn spawn_sprite_per_block(
+    chunk_index: ChunkPos,
+    game_world: &GameWorld,
+    rules: &AutotileRules,
+    world: &mut World,
+) {
+    let config = &game_world.config;
+    let block_size = config.block_size as f32;
+    let first_block = chunk_index.first_block(config);
+    for col_x in 0..config.chunk_width as i32 {
+        for y in 0..config.world_height as i32 {
+            let pos = first_block.offset(col_x, y);
+            let Block::Solid(block) = game_world.get_block(pos) else {
+                continue;
+            };
+            let mut sprite_bundle = SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(block_size, block_size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(pos.local_position(config).extend(2.)),
+                ..default()
+            };
+            match block {
+                SolidBlock::Stone | SolidBlock::Ore(_) => {
+                    sprite_bundle.sprite.color = block_color(block);
+                    world.spawn(sprite_bundle);
+                }
+                SolidBlock::Earth | SolidBlock::Surface(_) => {
+                    let (_, index, tint) =
+                        get_tile(block, &get_around_blocks(pos, game_world), rules);
+                    sprite_bundle.sprite.color = tint;
+                    world.spawn((sprite_bundle, TextureAtlas { index, ..default() }));
+                }
+            }
+        }
+    }
+}
+
+criterion_group!(benches, chunk_build);
+criterion_main!(benches);
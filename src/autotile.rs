@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use bevy::{log::warn, prelude::Resource};
+use serde::Deserialize;
+
+use crate::map::{tiles_atlas_layout, Block, SolidBlock};
+
+#[cfg(not(target_arch = "wasm32"))]
+const AUTOTILE_RULES_PATH: &str = "assets/config/autotile.ron";
+const DEFAULT_AUTOTILE_RULES: &str = include_str!("../assets/config/autotile.ron");
+
+//Bits of the neighbourhood mask, set when the neighbour is solid
+const N: u8 = 1;
+const NE: u8 = 2;
+const E: u8 = 4;
+const SE: u8 = 8;
+const S: u8 = 16;
+const SW: u8 = 32;
+const W: u8 = 64;
+const NW: u8 = 128;
+
+/// Atlas indices for every blob neighbourhood of the tiled block types. Loaded from
+/// `assets/config/autotile.ron` so tiles can be remapped without recompiling.
+#[derive(Resource, Clone, Debug, Deserialize)]
+pub struct AutotileRules {
+    pub surface: TileRules,
+    pub earth: TileRules,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TileRules {
+    pub default: usize,            //used for neighbourhoods missing from `tiles`
+    pub tiles: HashMap<u8, usize>, //blob mask to atlas index
+}
+
+impl AutotileRules {
+    /// Reads the rules from disk on native, on wasm (or when the file is missing, invalid or
+    /// breaks `validate`) the copy embedded in the binary is used.
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(content) = std::fs::read_to_string(AUTOTILE_RULES_PATH) {
+            match ron::from_str(&content)
+                .map_err(|e| e.to_string())
+                .and_then(|rules: AutotileRules| rules.validate().map(|_| rules))
+            {
+                Ok(rules) => return rules,
+                Err(e) => warn!("Invalid autotile rules, using defaults: {}", e),
+            }
+        }
+        ron::from_str(DEFAULT_AUTOTILE_RULES).expect("Embedded autotile rules are invalid")
+    }
+
+    /// Checks that every index, defaults included, is a tile of the atlas.
+    pub fn validate(&self) -> Result<(), String> {
+        let tile_count = tiles_atlas_layout().textures.len();
+        for (name, rules) in [("surface", &self.surface), ("earth", &self.earth)] {
+            let indices = std::iter::once(&rules.default).chain(rules.tiles.values());
+            if let Some(index) = indices.copied().find(|index| *index >= tile_count) {
+                return Err(format!(
+                    "{} tile {} is out of the {} tiles of the atlas",
+                    name, index, tile_count
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Atlas index of a textured block, picked from the blocks around it.
+    pub fn get_index(&self, block: SolidBlock, around_blocks: &[Block; 9]) -> usize {
+        let rules = match block {
+            SolidBlock::Surface(_) => &self.surface,
+            _ => &self.earth,
+        };
+        let mask = blob_mask(get_neighbourhood_mask(around_blocks));
+        rules.tiles.get(&mask).copied().unwrap_or(rules.default)
+    }
+}
+
+/// Mask of the solid blocks around the center of a `get_around_blocks` neighbourhood.
+pub fn get_neighbourhood_mask(around_blocks: &[Block; 9]) -> u8 {
+    //Same order as `get_around_blocks`: up left to down right, row by row
+    const BITS: [u8; 9] = [NW, N, NE, W, 0, E, SW, S, SE];
+    around_blocks
+        .iter()
+        .zip(BITS)
+        .filter(|(block, _)| **block != Block::Air)
+        .fold(0, |mask, (_, bit)| mask | bit)
+}
+
+/// Drops the corners that don't change the tile: a corner only shows when both sides next to
+/// it are solid. What is left is one of the 47 blob neighbourhoods.
+pub fn blob_mask(mask: u8) -> u8 {
+    let mut blob = mask & (N | E | S | W);
+    for (corner, sides) in [(NE, N | E), (SE, S | E), (SW, S | W), (NW, N | W)] {
+        if mask & corner != 0 && mask & sides == sides {
+            blob |= corner;
+        }
+    }
+    blob
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome::Biome;
+
+    const SURFACE: SolidBlock = SolidBlock::Surface(Biome::Meadow);
+    const EARTH: SolidBlock = SolidBlock::Earth;
+
+    fn default_rules() -> AutotileRules {
+        ron::from_str(DEFAULT_AUTOTILE_RULES).unwrap()
+    }
+
+    //Neighbourhood with the given neighbours solid, around a solid center
+    fn around_blocks(mask: u8) -> [Block; 9] {
+        let solid = |bit: u8| {
+            if mask & bit != 0 {
+                Block::Solid(SolidBlock::Stone)
+            } else {
+                Block::Air
+            }
+        };
+        [
+            solid(NW),
+            solid(N),
+            solid(NE),
+            solid(W),
+            Block::Solid(SolidBlock::Stone),
+            solid(E),
+            solid(SW),
+            solid(S),
+            solid(SE),
+        ]
+    }
+
+    fn blob_masks() -> Vec<u8> {
+        let mut masks: Vec<u8> = (0..=u8::MAX).map(blob_mask).collect();
+        masks.sort();
+        masks.dedup();
+        masks
+    }
+
+    #[test]
+    fn every_neighbourhood_reduces_to_one_of_47_blob_masks() {
+        assert_eq!(blob_masks().len(), 47);
+    }
+
+    #[test]
+    fn blob_mask_is_stable() {
+        for mask in 0..=u8::MAX {
+            assert_eq!(blob_mask(blob_mask(mask)), blob_mask(mask));
+        }
+    }
+
+    #[test]
+    fn neighbourhood_mask_round_trips() {
+        for mask in 0..=u8::MAX {
+            assert_eq!(get_neighbourhood_mask(&around_blocks(mask)), mask);
+        }
+    }
+
+    #[test]
+    fn default_rules_cover_every_possible_blob_mask() {
+        let rules = default_rules();
+        assert_eq!(rules.earth.tiles.len(), 47);
+        for mask in blob_masks() {
+            assert!(
+                rules.earth.tiles.contains_key(&mask),
+                "missing mask {}",
+                mask
+            );
+        }
+        //Surface blocks have air above them
+        let surface_masks: Vec<u8> = blob_masks().into_iter().filter(|m| m & N == 0).collect();
+        assert_eq!(rules.surface.tiles.len(), surface_masks.len());
+        for mask in surface_masks {
+            assert!(
+                rules.surface.tiles.contains_key(&mask),
+                "missing surface mask {}",
+                mask
+            );
+        }
+    }
+
+    #[test]
+    fn every_neighbourhood_maps_to_an_atlas_tile() {
+        let rules = default_rules();
+        let tile_count = tiles_atlas_layout().textures.len();
+        for block in [SURFACE, EARTH] {
+            for mask in 0..=u8::MAX {
+                let index = rules.get_index(block, &around_blocks(mask));
+                assert!(index < tile_count, "mask {} maps to tile {}", mask, index);
+                //Corners hidden by an open side never change the tile
+                assert_eq!(
+                    index,
+                    rules.get_index(block, &around_blocks(blob_mask(mask)))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_rules_pick_the_expected_tiles() {
+        let rules = default_rules();
+        let all = u8::MAX;
+        let cases = [
+            (SURFACE, all & !(N | NE | NW), 1), //top
+            (SURFACE, E | SE | S, 0),           //top left
+            (SURFACE, W | SW | S, 3),           //top right
+            (SURFACE, S, 31),                   //pillar top
+            (SURFACE, E | W, 29),               //one block thick platform
+            (SURFACE, 0, 31),                   //floating block
+            (EARTH, all, 8),                    //center
+            (EARTH, all & !W & !NW & !SW, 7),   //left
+            (EARTH, all & !E & !NE & !SE, 10),  //right
+            (EARTH, all & !NW, 12),             //inner up left corner
+            (EARTH, all & !NE, 11),             //inner up right corner
+            (EARTH, all & !SE, 4),              //inner down right corner
+            (EARTH, all & !SW, 5),              //inner down left corner
+            (EARTH, N | NE | E, 21),            //bottom left
+        ];
+        for (block, mask, expected) in cases {
+            assert_eq!(
+                rules.get_index(block, &around_blocks(mask)),
+                expected,
+                "mask {}",
+                mask
+            );
+        }
+    }
+
+    #[test]
+    fn the_default_rules_are_valid() {
+        assert_eq!(default_rules().validate(), Ok(()));
+    }
+
+    #[test]
+    fn indices_past_the_atlas_are_rejected() {
+        let tile_count = tiles_atlas_layout().textures.len();
+        let mut rules = default_rules();
+        rules.earth.default = tile_count;
+        assert!(rules.validate().is_err());
+
+        let mut rules = default_rules();
+        rules.surface.tiles.insert(0, tile_count);
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    fn missing_masks_fall_back_to_the_default() {
+        let mut rules = default_rules();
+        rules.earth.tiles.clear();
+        assert_eq!(
+            rules.get_index(EARTH, &around_blocks(0)),
+            rules.earth.default
+        );
+    }
+}
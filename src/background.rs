@@ -0,0 +1,206 @@
+use bevy::{
+    prelude::*, render::camera::OrthographicProjection, sprite::Anchor, transform::TransformSystem,
+};
+
+use crate::{
+    camera::InGameCamera,
+    game::TimeOfDay,
+    game_world::GameWorld,
+    loading::RequiredAssets,
+    theme::{Theme, ThemedImage},
+    wraparound::WorldRebaseSet,
+    BACKGROUND_LAYERS, CANVAS_HEIGHT, CANVAS_WIDTH,
+};
+
+const BACKGROUND_TEXTURE_SIZE: Vec2 = Vec2::new(192., 128.);
+const SKY_Z: f32 = 0.; //the terrain starts at z 1, the background stays behind it
+
+//Far to near: scroll factor, texture scale and bottom edge in canvas pixels above the view bottom
+const PARALLAX_LAYERS: [(f32, f32, f32); 3] = [(0.1, 3., 60.), (0.25, 2., 0.), (0.5, 1.5, -80.)];
+
+//Keyframes over the time of day, in linear rgb
+const SKY_COLORS: [(f32, [f32; 3]); 6] = [
+    (0.2, [0.02, 0.02, 0.08]),
+    (0.27, [0.8, 0.35, 0.2]),
+    (0.35, [0.3, 0.6, 1.0]),
+    (0.65, [0.3, 0.6, 1.0]),
+    (0.73, [0.85, 0.3, 0.15]),
+    (0.8, [0.02, 0.02, 0.08]),
+];
+const LAYER_LIGHT: [(f32, [f32; 3]); 6] = [
+    (0.2, [0.2, 0.25, 0.45]),
+    (0.27, [1.0, 0.7, 0.6]),
+    (0.35, [1.0, 1.0, 1.0]),
+    (0.65, [1.0, 1.0, 1.0]),
+    (0.73, [1.0, 0.65, 0.5]),
+    (0.8, [0.2, 0.25, 0.45]),
+];
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(Startup, startup)
+            .add_systems(
+                Update,
+                (
+                    update_sky_color,
+                    apply_theme.run_if(resource_changed::<Theme>),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                follow_camera
+                    .after(WorldRebaseSet)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Sky;
+
+/// Row of background tiles that scrolls slower than the camera. Everything is in canvas
+/// pixels, the layer is scaled with the camera zoom so it always covers the view.
+#[derive(Component)]
+struct ParallaxLayer {
+    scroll_factor: f32,
+    tile_width: f32,
+    bottom: f32,
+    z: f32,
+}
+
+#[derive(Component)]
+struct BackgroundImage(ThemedImage);
+
+fn startup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_world: Res<GameWorld>,
+    theme: Res<Theme>,
+    mut required_assets: ResMut<RequiredAssets>,
+) {
+    let image = ThemedImage::load(&asset_server, "bgp_catdev/BackGrounds/Basic_BackGround");
+    required_assets.track_themed(&image);
+    let world_width_in_pixels = game_world.config.world_width_in_pixels() as f32;
+
+    commands.spawn((
+        Name::new("Sky"),
+        Sky,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., SKY_Z),
+            ..default()
+        },
+        BACKGROUND_LAYERS,
+    ));
+
+    for (index, (scroll_factor, scale, bottom)) in PARALLAX_LAYERS.into_iter().enumerate() {
+        let tile_width = BACKGROUND_TEXTURE_SIZE.x * scale;
+        //A whole turn around the world scrolls a whole number of tiles, so the wraparound has no seam
+        let tiles_per_turn = (world_width_in_pixels * scroll_factor / tile_width)
+            .round()
+            .max(1.);
+        let layer = ParallaxLayer {
+            scroll_factor: tiles_per_turn * tile_width / world_width_in_pixels,
+            tile_width,
+            bottom,
+            z: SKY_Z + 0.1 * (index + 1) as f32,
+        };
+        let tile_count = (CANVAS_WIDTH as f32 / tile_width).ceil() as usize + 1;
+
+        commands
+            .spawn((
+                Name::new(format!("Parallax layer {}", index)),
+                SpatialBundle::from_transform(Transform::from_xyz(0., 0., layer.z)),
+                layer,
+                BACKGROUND_LAYERS,
+            ))
+            .with_children(|parent| {
+                for tile in 0..tile_count {
+                    parent.spawn((
+                        BackgroundImage(image.clone()),
+                        SpriteBundle {
+                            texture: image.get(*theme),
+                            sprite: Sprite {
+                                anchor: Anchor::BottomLeft,
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(tile as f32 * tile_width, 0., 0.)
+                                .with_scale(Vec3::new(scale, scale, 1.)),
+                            ..default()
+                        },
+                        BACKGROUND_LAYERS,
+                    ));
+                }
+            });
+    }
+}
+
+fn follow_camera(
+    cam_query: Query<(&Transform, &OrthographicProjection), With<InGameCamera>>,
+    mut sky_query: Query<&mut Transform, (With<Sky>, Without<InGameCamera>)>,
+    mut layer_query: Query<(&mut Transform, &ParallaxLayer), (Without<Sky>, Without<InGameCamera>)>,
+) {
+    let Ok((cam_transform, projection)) = cam_query.get_single() else {
+        return;
+    };
+    let camera = cam_transform.translation.truncate();
+    let scale = projection.scale;
+    let view_bottom_left = -Vec2::new(CANVAS_WIDTH as f32, CANVAS_HEIGHT as f32) / 2.;
+
+    for mut transform in sky_query.iter_mut() {
+        transform.translation = camera.extend(SKY_Z);
+        transform.scale = Vec3::new(scale, scale, 1.);
+    }
+
+    for (mut transform, layer) in layer_query.iter_mut() {
+        let scroll = (camera.x * layer.scroll_factor).rem_euclid(layer.tile_width);
+        let offset = view_bottom_left + Vec2::new(-scroll, layer.bottom);
+        transform.translation = (camera + offset * scale).extend(layer.z);
+        transform.scale = Vec3::new(scale, scale, 1.);
+    }
+}
+
+fn update_sky_color(
+    time_of_day: Res<TimeOfDay>,
+    mut sky_query: Query<&mut Sprite, With<Sky>>,
+    mut image_query: Query<&mut Sprite, (With<BackgroundImage>, Without<Sky>)>,
+) {
+    for mut sprite in sky_query.iter_mut() {
+        sprite.color = sample_keyframes(&SKY_COLORS, time_of_day.0);
+    }
+    let light = sample_keyframes(&LAYER_LIGHT, time_of_day.0);
+    for mut sprite in image_query.iter_mut() {
+        sprite.color = light;
+    }
+}
+
+fn apply_theme(theme: Res<Theme>, mut image_query: Query<(&mut Handle<Image>, &BackgroundImage)>) {
+    for (mut texture, image) in image_query.iter_mut() {
+        *texture = image.0.get(*theme);
+    }
+}
+
+//Linear blend between the keyframes around `time`, wrapping from the last one to the first
+fn sample_keyframes(keyframes: &[(f32, [f32; 3])], time: f32) -> Color {
+    let time = time.rem_euclid(1.);
+    let next = keyframes
+        .iter()
+        .position(|(keyframe_time, _)| *keyframe_time > time)
+        .unwrap_or(keyframes.len());
+    let (mut start_time, start) = keyframes[(next + keyframes.len() - 1) % keyframes.len()];
+    let (mut end_time, end) = keyframes[next % keyframes.len()];
+    if next == 0 {
+        start_time -= 1.;
+    }
+    if next == keyframes.len() {
+        end_time += 1.;
+    }
+    let blend = ((time - start_time) / (end_time - start_time)).clamp(0., 1.);
+    let [r, g, b] = Vec3::from(start).lerp(Vec3::from(end), blend).to_array();
+    Color::linear_rgb(r, g, b)
+}
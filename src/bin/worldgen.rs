@@ -0,0 +1,114 @@
+//! Renders a whole generated world to a PNG, one pixel per block, without opening a window.
+//! Pickables are marked red and the enemies waiting in structures magenta.
+//!
+//! `cargo run --bin worldgen -- --seed <seed> --config <path> --out <path>`
+//!
+//! Takes the same `--seed` and `--config` arguments as the game. The image goes to
+//! `example_images/world.png` unless `--out` is given.
+
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_github_ci_template::{
+    biome::Biome,
+    config::WorldConfig,
+    coordinates::BlockPos,
+    fixtures::seeded_pickables,
+    game_world::GameWorld,
+    map::{Block, SolidBlock},
+    pickables::Pickable,
+    seed::WorldSeed,
+    tilemap::block_color,
+};
+use noise::utils::NoiseImage;
+
+const DEFAULT_OUT_PATH: &str = "example_images/world.png";
+
+const SKY_COLOR: [u8; 4] = [150, 200, 255, 255];
+const CAVE_COLOR: [u8; 4] = [30, 25, 35, 255];
+const EARTH_COLOR: [u8; 4] = [120, 80, 50, 255];
+const PICKABLE_COLOR: [u8; 4] = [255, 0, 0, 255];
+const ENEMY_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+fn main() {
+    let seed = WorldSeed::from_env();
+    let config = WorldConfig::load();
+    let out_path = read_out_argument().unwrap_or_else(|| PathBuf::from(DEFAULT_OUT_PATH));
+    println!("World seed: {}", seed.0);
+
+    let game_world = GameWorld::generate(&seed, config);
+    let pickables = seeded_pickables(&seed, &game_world);
+
+    let image = render_world(&game_world, &pickables);
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create directories.");
+    }
+    image.write_to_file(&out_path);
+}
+
+fn render_world(game_world: &GameWorld, pickables: &[Pickable]) -> NoiseImage {
+    let config = &game_world.config;
+    let width = config.world_width();
+    let height = config.world_height;
+    let mut image = NoiseImage::new(width, height);
+
+    for x in 0..width as i32 {
+        let surface = game_world.surface_at(BlockPos::new(x, 0));
+        for y in 0..height as i32 {
+            let pos = BlockPos::new(x, y);
+            let color = match game_world.get_block(pos) {
+                Block::Air if y <= surface.y => CAVE_COLOR,
+                Block::Air => SKY_COLOR,
+                Block::Solid(block) => solid_color(block),
+            };
+            //Image rows go from the top down, world rows from the bottom up
+            image.set_value(x as usize, (height as i32 - 1 - y) as usize, color);
+        }
+    }
+
+    let enemies = game_world
+        .structures()
+        .iter()
+        .flat_map(|structure| &structure.enemies)
+        .map(|(pos, _)| (*pos, ENEMY_COLOR));
+    let markers = pickables
+        .iter()
+        .map(|pickable| (pickable.pos, PICKABLE_COLOR))
+        .chain(enemies);
+    for (pos, color) in markers {
+        if pos.is_inside(config) {
+            image.set_value(pos.x as usize, (height as i32 - 1 - pos.y) as usize, color);
+        }
+    }
+    image
+}
+
+fn solid_color(block: SolidBlock) -> [u8; 4] {
+    match block {
+        SolidBlock::Surface(biome) => biome_color(biome),
+        SolidBlock::Earth => EARTH_COLOR,
+        SolidBlock::Stone | SolidBlock::Ore(_) => block_color(block).to_srgba().to_u8_array(),
+    }
+}
+
+fn biome_color(biome: Biome) -> [u8; 4] {
+    match biome {
+        Biome::Snow => [240, 245, 255, 255],
+        Biome::Meadow => [70, 170, 60, 255],
+        Biome::Swamp => [60, 100, 70, 255],
+        Biome::Desert => [230, 200, 110, 255],
+    }
+}
+
+fn read_out_argument() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--out" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg.strip_prefix("--out=") {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
@@ -0,0 +1,144 @@
+use bevy::color::Color;
+
+use crate::{enemy::SlimeKind, pickables::PickableItemType};
+
+const BIOME_BLEND: f32 = 0.08; //half width, in biome noise units, of the blending zone between biomes
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Snow,
+    Meadow,
+    Swamp,
+    Desert,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TilesetVariant {
+    Standard,
+    White,
+}
+
+impl Biome {
+    //Ordered as they show up along the biome noise, so neighbours in the list are neighbours in the world
+    pub const ALL: [Biome; 4] = [Biome::Snow, Biome::Meadow, Biome::Swamp, Biome::Desert];
+
+    //Range of the biome noise covered by each biome
+    fn noise_band(&self) -> (f32, f32) {
+        match self {
+            Biome::Snow => (-1.0, -0.2),
+            Biome::Meadow => (-0.2, 0.1),
+            Biome::Swamp => (0.1, 0.3),
+            Biome::Desert => (0.3, 1.0),
+        }
+    }
+
+    /// How far the surface strays from the floor median, as a factor of `floor_threshold`.
+    pub fn height_amplitude(&self) -> f32 {
+        match self {
+            Biome::Snow => 1.6,
+            Biome::Meadow => 1.0,
+            Biome::Swamp => 0.25,
+            Biome::Desert => 0.5,
+        }
+    }
+
+    pub fn tileset(&self) -> TilesetVariant {
+        match self {
+            Biome::Snow => TilesetVariant::White,
+            _ => TilesetVariant::Standard,
+        }
+    }
+
+    pub fn surface_tint(&self) -> Color {
+        match self {
+            Biome::Snow | Biome::Meadow => Color::WHITE,
+            Biome::Swamp => Color::linear_rgb(0.45, 0.6, 0.4),
+            Biome::Desert => Color::linear_rgb(1.0, 0.85, 0.45),
+        }
+    }
+
+    /// Average chance of a surface block carrying a prop.
+    pub fn prop_density(&self) -> f32 {
+        match self {
+            Biome::Snow => 0.15,
+            Biome::Meadow => 0.45,
+            Biome::Swamp => 0.35,
+            Biome::Desert => 0.1,
+        }
+    }
+
+    /// Props of the tileset atlas that show up on the biome's surface, with their weights.
+    pub fn props(&self) -> &'static [(usize, u32)] {
+        //6: grass, 13: sprout, 20: tuft, 27: flower, 32 and 33: rocks, 34: mushroom
+        match self {
+            Biome::Snow => &[(32, 3), (33, 3), (20, 1)],
+            Biome::Meadow => &[(6, 4), (20, 3), (27, 3), (13, 2), (32, 1)],
+            Biome::Swamp => &[(34, 3), (13, 3), (20, 2), (6, 1)],
+            Biome::Desert => &[(32, 3), (33, 2), (13, 1)],
+        }
+    }
+
+    pub fn pickable_weights(&self) -> [(PickableItemType, u32); 4] {
+        match self {
+            Biome::Snow => [
+                (PickableItemType::Diammond, 3),
+                (PickableItemType::Gem, 3),
+                (PickableItemType::Emmerald, 1),
+                (PickableItemType::Gold, 2),
+            ],
+            Biome::Meadow => [
+                (PickableItemType::Diammond, 1),
+                (PickableItemType::Gem, 2),
+                (PickableItemType::Emmerald, 3),
+                (PickableItemType::Gold, 6),
+            ],
+            Biome::Swamp => [
+                (PickableItemType::Diammond, 1),
+                (PickableItemType::Gem, 1),
+                (PickableItemType::Emmerald, 6),
+                (PickableItemType::Gold, 2),
+            ],
+            Biome::Desert => [
+                (PickableItemType::Diammond, 1),
+                (PickableItemType::Gem, 3),
+                (PickableItemType::Emmerald, 1),
+                (PickableItemType::Gold, 8),
+            ],
+        }
+    }
+
+    pub fn enemy_spawn_table(&self) -> &'static [(SlimeKind, u32)] {
+        match self {
+            Biome::Snow => &[(SlimeKind::Green, 1)],
+            Biome::Meadow => &[(SlimeKind::Green, 3), (SlimeKind::Red, 1)],
+            Biome::Swamp => &[(SlimeKind::Green, 1), (SlimeKind::Red, 1)],
+            Biome::Desert => &[(SlimeKind::Red, 1)],
+        }
+    }
+
+    /// Weight of every biome in `Biome::ALL` for a biome noise value. Weights add up to 1 and
+    /// fade linearly across the borders, so anything blended with them has no seams.
+    pub fn weights(noise_value: f32) -> [f32; 4] {
+        let noise_value = noise_value.clamp(-1., 1.);
+        let mut weights = Biome::ALL.map(|biome| {
+            let (low, high) = biome.noise_band();
+            let distance_inside = (noise_value - low).min(high - noise_value);
+            ((distance_inside + BIOME_BLEND) / (2. * BIOME_BLEND)).clamp(0., 1.)
+        });
+        let total: f32 = weights.iter().sum();
+        if total > 0. {
+            weights.iter_mut().for_each(|w| *w /= total);
+        }
+        weights
+    }
+
+    pub fn dominant(weights: &[f32; 4]) -> Biome {
+        let mut dominant = 0;
+        for (index, weight) in weights.iter().enumerate() {
+            if *weight > weights[dominant] {
+                dominant = index;
+            }
+        }
+        Biome::ALL[dominant]
+    }
+}
@@ -1,11 +1,12 @@
 use bevy::{
     app::{Plugin, Startup, Update},
-    asset::{AssetServer, Assets, Handle},
+    asset::Assets,
     input::{mouse::MouseWheel, ButtonInput},
-    math::Vec3,
+    math::{Vec2, Vec3},
     prelude::{
-        default, Camera2dBundle, Commands, Component, EventReader, KeyCode, Query, Res, ResMut,
-        Transform, With, Without,
+        default, in_state, Camera2dBundle, Commands, Component, EventReader, GlobalTransform,
+        IntoSystemConfigs, KeyCode, Query, Res, ResMut, Resource, Transform, Trigger, With,
+        Without,
     },
     render::{
         camera::{Camera, OrthographicProjection, RenderTarget},
@@ -16,13 +17,18 @@ use bevy::{
         view::Msaa,
     },
     sprite::SpriteBundle,
-    window::WindowResized,
+    window::{PrimaryWindow, Window, WindowResized},
 };
 
 use crate::{
-    character::Character, map::Chunk, BACKGROUND_LAYERS, BLOCK_SIZE, CAMERA_REGULAR_SPEED,
-    CANVAS_HEIGHT, CANVAS_WIDTH, CHARACTER_MOVEMENT_SPEED, CHARACTER_ROAMING_THRESHOLD,
-    CHUNKS_TO_LOAD, CHUNK_WIDTH, HIGH_RES_LAYERS, PIXEL_PERFECT_LAYERS, WORLD_WIDTH,
+    character::{Character, CharacterRespawnEvent},
+    config::WorldConfig,
+    game::{AppState, SpawnWorld},
+    game_world::GameWorld,
+    map::{Chunk, ChunkLoader},
+    wraparound::{WorldRebasedEvent, WorldWrap},
+    BACKGROUND_LAYERS, CANVAS_HEIGHT, CANVAS_WIDTH, CHARACTER_ROAMING_THRESHOLD, HIGH_RES_LAYERS,
+    PIXEL_PERFECT_LAYERS,
 };
 
 #[derive(Component)]
@@ -31,15 +37,11 @@ struct OuterCamera;
 #[derive(Component)]
 struct Canvas;
 
-#[derive(Component)]
-struct Background;
-
 #[derive(Component)]
 pub struct InGameCamera {
     pub is_going_right: bool,
     pub whole_turn_at: f32,
     pub translation: Vec3,
-    pub chunk_unload_after: f32,
     pub state: CameraState,
     pub char_roaming_threshold: f32,
     pub catching_up: f32,
@@ -48,6 +50,26 @@ pub struct InGameCamera {
     pub zoom_min_max: (f32, f32),
 }
 
+impl InGameCamera {
+    pub fn new(config: &WorldConfig) -> Self {
+        InGameCamera {
+            is_going_right: true,
+            whole_turn_at: config.world_width_in_pixels() as f32,
+            translation: Vec3::ZERO,
+            state: CameraState::Waiting,
+            char_roaming_threshold: CHARACTER_ROAMING_THRESHOLD as f32,
+            catching_up: 0.,
+            speed: 0.,
+            zoom_step: -0.1,
+            zoom_min_max: (0.4, 1.5),
+        }
+    }
+}
+
+/// Position of the mouse cursor in the in-game world, if it is over the window.
+#[derive(Resource, Default)]
+pub struct CursorWorldPosition(pub Option<Vec2>);
+
 #[derive(PartialEq, Default)]
 pub enum CameraState {
     #[default]
@@ -61,16 +83,24 @@ pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.insert_resource(Msaa::Off)
+            .init_resource::<CursorWorldPosition>()
+            .observe(on_world_rebased)
+            .observe(on_character_respawn)
             .add_systems(Startup, startup)
-            .add_systems(Update, (fit_canvas, move_camera));
+            .add_systems(SpawnWorld, reset_camera)
+            .add_systems(
+                Update,
+                (
+                    fit_canvas,
+                    (move_camera, update_cursor_position.after(move_camera))
+                        .run_if(in_state(AppState::Playing)),
+                ),
+            );
     }
 }
 
-fn startup(
-    mut commands: Commands,
-    mut images: ResMut<Assets<Image>>,
-    asset_server: Res<AssetServer>,
-) {
+fn startup(mut commands: Commands, mut images: ResMut<Assets<Image>>, game_world: Res<GameWorld>) {
+    let config = &game_world.config;
     let canvas_size = Extent3d {
         width: CANVAS_WIDTH as u32,
         height: CANVAS_HEIGHT as u32,
@@ -125,34 +155,14 @@ fn startup(
             },
             ..default()
         },
-        InGameCamera {
-            is_going_right: true,
-            whole_turn_at: (WORLD_WIDTH * BLOCK_SIZE) as f32,
-            translation: Vec3::ZERO,
-            chunk_unload_after: (((CHUNKS_TO_LOAD / 2) * CHUNK_WIDTH * BLOCK_SIZE) as f32),
-            state: CameraState::Waiting,
-            char_roaming_threshold: CHARACTER_ROAMING_THRESHOLD as f32,
-            catching_up: 0.,
-            speed: 0.,
-            zoom_step: -0.1,
-            zoom_min_max: (0.4, 1.5),
+        InGameCamera::new(config),
+        ChunkLoader {
+            radius_in_chunks: config.chunks_to_load / 2,
         },
+        WorldWrap,
         PIXEL_PERFECT_LAYERS,
     ));
 
-    let bg_image: Handle<Image> = asset_server.load("bgp_catdev/BackGrounds/Basic_BackGround.png");
-
-    commands.spawn((
-        SpriteBundle {
-            texture: bg_image,
-            transform: Transform::from_scale(Vec3::new(6., 5.25, 0.0)),
-            ..default()
-        },
-        Canvas,
-        Background,
-        BACKGROUND_LAYERS,
-    ));
-
     commands.spawn((
         SpriteBundle {
             texture: image_handle,
@@ -190,16 +200,16 @@ fn move_camera(
     >,
     keys: Res<ButtonInput<KeyCode>>,
     char_query: Query<&Transform, (With<Character>, Without<InGameCamera>)>,
-    mut bg_query: Query<
-        &mut Transform,
-        (With<Background>, Without<InGameCamera>, Without<Character>),
-    >,
     mut evr_scroll: EventReader<MouseWheel>,
+    game_world: Res<GameWorld>,
 ) {
+    let config = &game_world.config;
     let (mut transform, mut projection, mut camera) = cam_query.single_mut();
+    let world_width_in_pixels = config.world_width_in_pixels() as f32;
+    let camera_regular_speed = config.camera_regular_speed() as f32;
+    let character_movement_speed = config.character_movement_speed() as f32;
 
     let char = char_query.single();
-    let mut bg = bg_query.single_mut();
 
     for ev in evr_scroll.read() {
         projection.scale += ev.y * camera.zoom_step;
@@ -215,9 +225,9 @@ fn move_camera(
         if char_offset.abs() > camera.char_roaming_threshold {
             camera.is_going_right = char.translation.x > transform.translation.x;
             camera.whole_turn_at = if camera.is_going_right {
-                transform.translation.x + (WORLD_WIDTH * BLOCK_SIZE) as f32
+                transform.translation.x + world_width_in_pixels
             } else {
-                transform.translation.x - ((WORLD_WIDTH * BLOCK_SIZE) as f32)
+                transform.translation.x - world_width_in_pixels
             };
             camera.state = CameraState::CatchingUp;
             camera.catching_up = if (camera.is_going_right
@@ -231,9 +241,8 @@ fn move_camera(
         }
         return;
     } else if camera.state == CameraState::CatchingUp {
-        camera.speed = 10.
-            * CAMERA_REGULAR_SPEED as f32
-            * (projection.scale / ((CHARACTER_MOVEMENT_SPEED as f32) * 2.));
+        camera.speed =
+            10. * camera_regular_speed * (projection.scale / (character_movement_speed * 2.));
         camera.catching_up -= camera.speed.abs();
         if camera.catching_up <= 0. {
             camera.catching_up = 0.;
@@ -241,12 +250,11 @@ fn move_camera(
         }
     } else {
         if keys.pressed(KeyCode::ShiftLeft) {
-            camera.speed = (CAMERA_REGULAR_SPEED as f32)
-                * 5.
-                * (projection.scale / ((CHARACTER_MOVEMENT_SPEED as f32) * 2.));
+            camera.speed =
+                camera_regular_speed * 5. * (projection.scale / (character_movement_speed * 2.));
         } else {
-            camera.speed = CAMERA_REGULAR_SPEED as f32
-                * (projection.scale / ((CHARACTER_MOVEMENT_SPEED as f32) * 2.));
+            camera.speed =
+                camera_regular_speed * (projection.scale / (character_movement_speed * 2.));
         }
     }
 
@@ -259,7 +267,58 @@ fn move_camera(
         camera.state = CameraState::Waiting;
     }
     transform.translation.y = char.translation.y;
-    camera.translation = transform.translation.clone();
+    camera.translation = transform.translation;
+}
+
+//Back over the spawn point, waiting for the character to move, with the zoom kept
+fn reset_camera(
+    mut cam_query: Query<(&mut Transform, &mut InGameCamera)>,
+    game_world: Res<GameWorld>,
+) {
+    let config = &game_world.config;
+    let spawn_point = game_world.spawn_point().to_world(config).0;
+    for (mut transform, mut camera) in cam_query.iter_mut() {
+        transform.translation.x = spawn_point.x;
+        transform.translation.y = spawn_point.y;
+        *camera = InGameCamera::new(config);
+        camera.whole_turn_at += spawn_point.x;
+        camera.translation = transform.translation;
+    }
+}
+
+fn on_character_respawn(
+    _: Trigger<CharacterRespawnEvent>,
+    cam_query: Query<(&mut Transform, &mut InGameCamera)>,
+    game_world: Res<GameWorld>,
+) {
+    reset_camera(cam_query, game_world);
+}
+
+//The camera keeps where its turn ends outside of its transform
+fn on_world_rebased(trigger: Trigger<WorldRebasedEvent>, mut cam_query: Query<&mut InGameCamera>) {
+    let offset = trigger.event().offset;
+    for mut camera in cam_query.iter_mut() {
+        camera.whole_turn_at += offset;
+        camera.translation.x += offset;
+    }
+}
+
+//The cursor goes through two cameras: the outer one shows the canvas, the in-game one renders to it
+fn update_cursor_position(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    outer_cam_query: Query<(&Camera, &GlobalTransform), With<OuterCamera>>,
+    cam_query: Query<(&Transform, &OrthographicProjection), With<InGameCamera>>,
+    mut cursor_position: ResMut<CursorWorldPosition>,
+) {
+    let (outer_camera, outer_camera_transform) = outer_cam_query.single();
+    let (transform, projection) = cam_query.single();
 
-    bg.translation = transform.translation.clone();
+    cursor_position.0 = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor| outer_camera.viewport_to_world_2d(outer_camera_transform, cursor))
+        .map(|canvas_position| {
+            transform.translation.truncate() + canvas_position * projection.scale
+        });
 }
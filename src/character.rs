@@ -11,13 +11,22 @@ use bevy::{
 use bevy_rapier2d::prelude::*;
 
 use crate::{
+    combat::Knockback,
     control::CharacterControlInput,
+    digging::BlockInventory,
+    game::{AppState, DespawnWorld, SpawnWorld},
+    game_world::GameWorld,
+    health::{Died, HealthPoints, HitInvulnerability, Invulnerable},
+    loading::RequiredAssets,
+    map::ChunkLoader,
     pickables::{PlacedPickable, PlacedPickableCollected},
-    GameWorld, BLOCK_SIZE, CHARACTER_JUMP_SPEED, CHARACTER_MOVEMENT_SPEED, CHARACTER_SIZE, GRAVITY,
-    PIXEL_PERFECT_LAYERS, WORLD_BOTTOM_OFFSET_IN_PIXELS, WORLD_CENTER_COL,
+    wraparound::WorldWrap,
+    GRAVITY, PIXEL_PERFECT_LAYERS,
 };
 
 const GROUND_TIMER: f32 = 0.5;
+const INVULNERABILITY_AFTER_HIT: f32 = 1.;
+pub const STARTING_COINS: u64 = 50; //coins in the pouch of a new character
 
 #[derive(Debug, Default, PartialEq)]
 enum CharacterState {
@@ -26,6 +35,7 @@ enum CharacterState {
     Walking,
     Jumping,
     Falling,
+    Dead,
 }
 
 impl CharacterState {
@@ -35,8 +45,14 @@ impl CharacterState {
             CharacterState::Walking => (8, 15),
             CharacterState::Jumping => (16, 16),
             CharacterState::Falling => (24, 24),
+            CharacterState::Dead => (32, 33),
         }
     }
+
+    //The death animation plays once and stays on its last frame
+    fn loops(&self) -> bool {
+        *self != CharacterState::Dead
+    }
 }
 
 #[derive(Component, Deref, DerefMut)]
@@ -45,83 +61,104 @@ struct AnimationTimer(Timer);
 #[derive(Component, Debug)]
 pub struct Character {
     movement_speed: f32,
+    jump_speed: f32,
     looking_left: bool,
     state: CharacterState,
 }
 
-#[derive(Component, Reflect)]
-pub struct CoinPouch(pub u64);
-
-#[derive(Component, Reflect)]
-pub struct HealthPoints {
-    pub max_full_hearts: u8,
-    pub current: u8,
-}
+impl Character {
+    pub fn is_looking_left(&self) -> bool {
+        self.looking_left
+    }
 
-impl HealthPoints {
-    fn full(hearts: u8) -> Self {
-        HealthPoints {
-            max_full_hearts: hearts,
-            current: hearts * 2,
-        }
+    pub fn is_dead(&self) -> bool {
+        self.state == CharacterState::Dead
     }
 }
 
+/// Triggered to bring the character back to life at the spawn point, with full health.
+#[derive(Event)]
+pub struct CharacterRespawnEvent;
+
+#[derive(Component, Reflect)]
+pub struct CoinPouch(pub u64);
+
 pub struct CharacterPlugin;
 
 impl Plugin for CharacterPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_type::<CoinPouch>()
-            .register_type::<HealthPoints>()
-            .add_systems(Startup, startup)
-            .add_systems(Update, (movement, animate, handle_collision));
+            .observe(on_character_died)
+            .observe(on_character_respawn)
+            .add_systems(Startup, load_textures)
+            .add_systems(SpawnWorld, startup)
+            .add_systems(DespawnWorld, despawn_character)
+            .add_systems(
+                Update,
+                (movement, animate, handle_collision).run_if(in_state(AppState::Playing)),
+            );
     }
 }
 
-fn startup(
+#[derive(Resource)]
+struct CharacterTexture {
+    image: Handle<Image>,
+    atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+fn load_textures(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    game_world: Res<GameWorld>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut required_assets: ResMut<RequiredAssets>,
 ) {
     let atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(16, 16), 8, 5, None, None);
-    let atlas_layout_handle = texture_atlases.add(atlas_layout);
-    let texture = asset_server.load("bgp_catdev/player_and_ui/Basic_Player.png");
+    let image = asset_server.load("bgp_catdev/player_and_ui/Basic_Player.png");
+    required_assets.track(image.clone());
+    commands.insert_resource(CharacterTexture {
+        image,
+        atlas_layout: texture_atlases.add(atlas_layout),
+    });
+}
+
+fn startup(
+    mut commands: Commands,
+    character_texture: Res<CharacterTexture>,
+    game_world: Res<GameWorld>,
+) {
+    let config = &game_world.config;
+    let character_size = config.character_size();
 
     commands.spawn((
         Character {
-            movement_speed: CHARACTER_MOVEMENT_SPEED as f32,
+            movement_speed: config.character_movement_speed() as f32,
+            jump_speed: config.character_jump_speed() as f32,
             looking_left: false,
             state: CharacterState::Idle,
         },
         SpriteBundle {
-            texture,
-            transform: Transform::from_xyz(
-                (BLOCK_SIZE as f32) * 0.5,
-                (((game_world.get_height_in_blocks(WORLD_CENTER_COL) as usize + 10) * BLOCK_SIZE)
-                    as f32)
-                    + WORLD_BOTTOM_OFFSET_IN_PIXELS as f32,
-                4.0,
+            texture: character_texture.image.clone(),
+            transform: Transform::from_translation(
+                game_world.spawn_point().to_world(config).0.extend(4.0),
             ),
             sprite: Sprite {
                 //anchor: bevy::sprite::Anchor::BottomCenter,
-                custom_size: Option::Some(Vec2::new(CHARACTER_SIZE as f32, CHARACTER_SIZE as f32)),
+                custom_size: Option::Some(Vec2::new(character_size as f32, character_size as f32)),
                 ..default()
             },
             ..default()
         },
         TextureAtlas {
-            layout: atlas_layout_handle,
+            layout: character_texture.atlas_layout.clone(),
             index: 0,
-            ..Default::default()
         },
         RigidBody::KinematicPositionBased,
-        Collider::capsule_y((CHARACTER_SIZE / 16) as f32, (CHARACTER_SIZE / 2) as f32),
+        Collider::capsule_y((character_size / 16) as f32, (character_size / 2) as f32),
         ActiveEvents::COLLISION_EVENTS,
         KinematicCharacterController {
             custom_shape: Option::Some((
-                Collider::cuboid((CHARACTER_SIZE / 3) as f32, (CHARACTER_SIZE / 2) as f32),
-                Vec2::new(0., CHARACTER_SIZE as f32 * 0.04),
+                Collider::cuboid((character_size / 3) as f32, (character_size / 2) as f32),
+                Vec2::new(0., character_size as f32 * 0.04),
                 0.,
             )),
             apply_impulse_to_dynamic_bodies: true,
@@ -138,12 +175,61 @@ fn startup(
         },
         //LockedAxes::ROTATION_LOCKED,
         AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-        CoinPouch(50),
+        CoinPouch(STARTING_COINS),
         HealthPoints::full(5),
+        HitInvulnerability(INVULNERABILITY_AFTER_HIT),
+        BlockInventory::default(),
+        ChunkLoader {
+            radius_in_chunks: config.character_chunks_to_load / 2,
+        },
+        WorldWrap,
         PIXEL_PERFECT_LAYERS,
     ));
 }
 
+fn despawn_character(query: Query<Entity, With<Character>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn on_character_died(
+    trigger: Trigger<Died>,
+    mut query: Query<(&mut Character, &mut TextureAtlas)>,
+) {
+    if let Ok((mut character, mut atlas)) = query.get_mut(trigger.entity()) {
+        character.state = CharacterState::Dead;
+        atlas.index = character.state.get_range().0;
+    }
+}
+
+fn on_character_respawn(
+    _: Trigger<CharacterRespawnEvent>,
+    mut query: Query<(
+        Entity,
+        &mut Character,
+        &mut HealthPoints,
+        &mut Transform,
+        &mut TextureAtlas,
+    )>,
+    game_world: Res<GameWorld>,
+    mut commands: Commands,
+) {
+    let Ok((entity, mut character, mut health, mut transform, mut atlas)) = query.get_single_mut()
+    else {
+        return;
+    };
+    let config = &game_world.config;
+    *health = HealthPoints::full(health.max_full_hearts);
+    character.state = CharacterState::Idle;
+    atlas.index = character.state.get_range().0;
+    transform.translation = game_world.spawn_point().to_world(config).0.extend(4.0);
+    commands
+        .entity(entity)
+        .remove::<(Knockback, Invulnerable)>()
+        .insert(Visibility::Inherited);
+}
+
 fn movement(
     mut query: Query<(
         &mut Character,
@@ -151,6 +237,7 @@ fn movement(
         Option<&KinematicCharacterControllerOutput>,
         &mut Sprite,
         &mut TextureAtlas,
+        Option<&Knockback>,
     )>,
     control_input: Res<CharacterControlInput>,
     time: Res<Time>,
@@ -164,14 +251,20 @@ fn movement(
         character_controller_output,
         mut sprite,
         mut atlas,
+        knockback,
     ) = query.single_mut();
 
+    //The dead only fall
+    let dead = character.is_dead();
+    let no_input = CharacterControlInput::default();
+    let control_input = if dead { &no_input } else { &*control_input };
+
     let mut move_delta = Vec2::new(
         control_input.x,
         0.0, //-(character.movement_speed * BLOCK_SIZE as f32 * delta_time),
     );
 
-    let jump_speed = control_input.y * CHARACTER_JUMP_SPEED as f32;
+    let jump_speed = control_input.y * character.jump_speed;
 
     if move_delta != Vec2::ZERO {
         move_delta /= move_delta.length();
@@ -198,7 +291,9 @@ fn movement(
 
     move_delta.y = *vertical_movement;
 
-    let next_state = if *vertical_movement > 0.4 {
+    let next_state = if dead {
+        CharacterState::Dead
+    } else if *vertical_movement > 0.4 {
         CharacterState::Jumping
     } else if *vertical_movement < -0.4 {
         CharacterState::Falling
@@ -250,8 +345,9 @@ fn movement(
         }
     }
 
+    let knockback = knockback.map_or(Vec2::ZERO, |knockback| knockback.0);
     character_controller.translation =
-        Some(move_delta * character.movement_speed as f32 * delta_time);
+        Some((move_delta * character.movement_speed + knockback) * delta_time);
 }
 
 fn animate(
@@ -263,7 +359,11 @@ fn animate(
     if timer.just_finished() {
         let (first, last) = character.state.get_range();
         atlas.index = if atlas.index == last {
-            first
+            if character.state.loops() {
+                first
+            } else {
+                last
+            }
         } else {
             atlas.index + 1
         };
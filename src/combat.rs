@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::KinematicCharacterControllerOutput;
+
+use crate::{
+    character::Character,
+    control::CharacterControlInput,
+    enemy::Slime,
+    game::AppState,
+    game_world::GameWorld,
+    health::{Damage, DamageKind, Invulnerable},
+};
+
+const CONTACT_DAMAGE: u8 = 1; //half hearts lost when a slime touches the character
+const STOMP_DAMAGE: u8 = 2;
+const ATTACK_DAMAGE: u8 = 2;
+const ATTACK_REACH_IN_BLOCKS: f32 = 1.5;
+const ATTACK_COOLDOWN: f32 = 0.4;
+const STOMP_NORMAL: f32 = 0.5; //how much a contact has to come from above to count as a stomp
+
+const BLINK_PERIOD: f32 = 0.1;
+
+//Knockback speeds in pixels/second, they fade out at `KNOCKBACK_DAMPING` per second
+const HIT_KNOCKBACK: Vec2 = Vec2::new(260., 120.);
+const STOMP_BOUNCE: Vec2 = Vec2::new(0., 380.);
+const KNOCKBACK_DAMPING: f32 = 8.;
+const KNOCKBACK_MIN_SPEED: f32 = 5.;
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(
+            Update,
+            (enemy_contact, attack, blink_invulnerable, damp_knockback)
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Push added on top of the movement of a character controller, in pixels/second.
+/// The movement systems add it to the controller's translation while it fades out.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Knockback(pub Vec2);
+
+//Contacts come from both controllers: the character running into a slime, or a slime
+//walking into the character. Landing on top of a slime stomps it.
+fn enemy_contact(
+    char_query: Query<(
+        Entity,
+        &Character,
+        &Transform,
+        Option<&KinematicCharacterControllerOutput>,
+        Has<Invulnerable>,
+    )>,
+    slime_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&KinematicCharacterControllerOutput>,
+            Has<Invulnerable>,
+        ),
+        With<Slime>,
+    >,
+    mut commands: Commands,
+) {
+    let Ok((char_entity, character, char_transform, char_output, mut char_invulnerable)) =
+        char_query.get_single()
+    else {
+        return;
+    };
+    if character.is_dead() {
+        return;
+    }
+
+    //Slime touched and the contact normal, pointing from the slime to the character
+    let mut contacts: Vec<(Entity, Vec2)> = Vec::new();
+    for collision in char_output.iter().flat_map(|output| &output.collisions) {
+        if slime_query.contains(collision.entity) {
+            let normal = collision.hit.details.map(|d| d.normal1).unwrap_or_default();
+            contacts.push((collision.entity, normal));
+        }
+    }
+    for (slime_entity, _, slime_output, _) in slime_query.iter() {
+        for collision in slime_output.iter().flat_map(|output| &output.collisions) {
+            if collision.entity == char_entity {
+                let normal = collision.hit.details.map(|d| d.normal1).unwrap_or_default();
+                contacts.push((slime_entity, -normal));
+            }
+        }
+    }
+
+    let mut stomped = Vec::new();
+    for (slime_entity, normal) in contacts {
+        let Ok((_, slime_transform, _, slime_invulnerable)) = slime_query.get(slime_entity) else {
+            continue;
+        };
+        if normal.y > STOMP_NORMAL {
+            if slime_invulnerable || stomped.contains(&slime_entity) {
+                continue;
+            }
+            stomped.push(slime_entity);
+            let stomp = hit(STOMP_DAMAGE, DamageKind::Stomp);
+            hurt_enemy(slime_entity, stomp, Vec2::ZERO, &mut commands);
+            commands.entity(char_entity).insert(Knockback(STOMP_BOUNCE));
+        } else if !char_invulnerable {
+            char_invulnerable = true;
+            let away = (char_transform.translation.x - slime_transform.translation.x).signum();
+            commands
+                .entity(char_entity)
+                .insert(Knockback(HIT_KNOCKBACK * Vec2::new(away, 1.)));
+            commands.trigger_targets(hit(CONTACT_DAMAGE, DamageKind::Contact), char_entity);
+        }
+    }
+}
+
+fn attack(
+    control_input: Res<CharacterControlInput>,
+    char_query: Query<(&Character, &Transform)>,
+    slime_query: Query<(Entity, &Transform, Has<Invulnerable>), With<Slime>>,
+    game_world: Res<GameWorld>,
+    time: Res<Time>,
+    mut cooldown: Local<f32>,
+    mut commands: Commands,
+) {
+    *cooldown -= time.delta_seconds();
+    if !control_input.attack || *cooldown > 0. {
+        return;
+    }
+    let Ok((character, char_transform)) = char_query.get_single() else {
+        return;
+    };
+    if character.is_dead() {
+        return;
+    }
+    *cooldown = ATTACK_COOLDOWN;
+
+    let block_size = game_world.config.block_size as f32;
+    let direction = if character.is_looking_left() { -1. } else { 1. };
+    let char_position = char_transform.translation.truncate();
+    for (slime_entity, slime_transform, slime_invulnerable) in slime_query.iter() {
+        let offset = slime_transform.translation.truncate() - char_position;
+        let in_reach = offset.x * direction >= 0.
+            && offset.x.abs() <= ATTACK_REACH_IN_BLOCKS * block_size
+            && offset.y.abs() <= block_size;
+        if !in_reach || slime_invulnerable {
+            continue;
+        }
+        let push = HIT_KNOCKBACK * Vec2::new(direction, 1.);
+        let attack = hit(ATTACK_DAMAGE, DamageKind::Attack);
+        hurt_enemy(slime_entity, attack, push, &mut commands);
+    }
+}
+
+fn hit(amount: u8, kind: DamageKind) -> Damage {
+    Damage { amount, kind }
+}
+
+//The damage goes last: an enemy it kills is despawned by its `Died` observer. A stomp and an
+//attack can land on the same enemy in one frame, so the second push may find it gone
+fn hurt_enemy(entity: Entity, damage: Damage, push: Vec2, commands: &mut Commands) {
+    commands.entity(entity).try_insert(Knockback(push));
+    commands.trigger_targets(damage, entity);
+}
+
+fn blink_invulnerable(
+    mut query: Query<(&Invulnerable, &mut Visibility)>,
+    mut visibility_query: Query<&mut Visibility, Without<Invulnerable>>,
+    mut ended: RemovedComponents<Invulnerable>,
+) {
+    for (invulnerable, mut visibility) in query.iter_mut() {
+        let blink = (invulnerable.0.elapsed_secs() / BLINK_PERIOD) as u32 % 2 == 1;
+        *visibility = if blink {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+    for entity in ended.read() {
+        if let Ok(mut visibility) = visibility_query.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn damp_knockback(
+    mut query: Query<(Entity, &mut Knockback)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut knockback) in query.iter_mut() {
+        knockback.0 *= (-KNOCKBACK_DAMPING * time.delta_seconds()).exp();
+        if knockback.0.length() < KNOCKBACK_MIN_SPEED {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
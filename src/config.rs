@@ -0,0 +1,175 @@
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_CONFIG_PATH: &str = "assets/config/world.ron";
+
+/// World dimensions and pacing. Loaded from `assets/config/world.ron` (or `--config <path>`)
+/// so the world can be tweaked without recompiling. The game reads it from `GameWorld::config`,
+/// so the world and the systems working on it never go by different settings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub block_size: usize,               //block size in pixels (height and width)
+    pub chunk_width: usize,              //chunk width in blocks
+    pub world_height: usize,             //world height in blocks
+    pub chunks_to_load: usize,           //chunks kept loaded around the camera
+    pub character_chunks_to_load: usize, //chunks kept loaded around the character
+    pub day_duration_in_seconds: usize,  //a whole turn around the world takes a day
+    pub map_movement_speed_in_blocks: usize, //camera speed in blocks/second
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig {
+            block_size: 16,
+            chunk_width: 16,
+            world_height: 128,
+            chunks_to_load: 16,
+            character_chunks_to_load: 6,
+            day_duration_in_seconds: 4 * 60,
+            map_movement_speed_in_blocks: 4,
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Reads the config file from disk on native, on wasm the default file is embedded in the binary.
+    /// Missing or invalid files, and values that break `validate`, fall back to the defaults.
+    pub fn load() -> Self {
+        let Some(content) = read_config_file() else {
+            return WorldConfig::default();
+        };
+        ron::from_str(&content)
+            .map_err(|e| e.to_string())
+            .and_then(|config: WorldConfig| config.validate().map(|_| config))
+            .unwrap_or_else(|e| {
+                warn!("Invalid world config, using defaults: {}", e);
+                WorldConfig::default()
+            })
+    }
+
+    /// Checks the invariants the chunks and the wraparound rely on: every size is positive, the
+    /// world is made of whole chunks and no more chunks are kept loaded than the world has.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.block_size == 0 || self.chunk_width == 0 || self.world_height == 0 {
+            return Err("block_size, chunk_width and world_height must be positive".to_string());
+        }
+        let world_width = self.world_width();
+        if world_width == 0 || !world_width.is_multiple_of(self.chunk_width) {
+            return Err(format!(
+                "the world width ({} blocks) must be a positive multiple of chunk_width ({})",
+                world_width, self.chunk_width
+            ));
+        }
+        let chunk_count = self.chunk_count();
+        if self.chunks_to_load > chunk_count || self.character_chunks_to_load > chunk_count {
+            return Err(format!(
+                "chunks_to_load and character_chunks_to_load can't be over the {} chunks of the world",
+                chunk_count
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn world_width(&self) -> usize {
+        self.day_duration_in_seconds * self.map_movement_speed_in_blocks
+    }
+
+    pub fn world_width_in_pixels(&self) -> usize {
+        self.world_width() * self.block_size
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.world_width() / self.chunk_width
+    }
+
+    pub fn chunk_initial_offset(&self) -> usize {
+        self.chunk_count() / 2
+    }
+
+    pub fn chunk_width_in_pixels(&self) -> usize {
+        self.chunk_width * self.block_size
+    }
+
+    pub fn floor_median(&self) -> f32 {
+        (self.world_height as f32) * 0.5
+    }
+
+    pub fn floor_threshold(&self) -> f32 {
+        self.floor_median() * 0.5
+    }
+
+    pub fn world_bottom_offset(&self) -> i32 {
+        -(self.world_height as i32 / 2)
+    }
+
+    pub fn world_bottom_offset_in_pixels(&self) -> i32 {
+        self.world_bottom_offset() * self.block_size as i32
+    }
+
+    //camera speed in pixels/second
+    pub fn camera_regular_speed(&self) -> usize {
+        self.block_size * self.map_movement_speed_in_blocks
+    }
+
+    pub fn character_size(&self) -> usize {
+        self.block_size * 2
+    }
+
+    pub fn character_movement_speed(&self) -> usize {
+        self.block_size * self.map_movement_speed_in_blocks * 2
+    }
+
+    pub fn character_jump_speed(&self) -> usize {
+        self.character_movement_speed() / 30
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_config_file() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    let mut path = DEFAULT_CONFIG_PATH.to_string();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            path = args.next()?;
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = value.to_string();
+        }
+    }
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_config_file() -> Option<String> {
+    Some(include_str!("../assets/config/world.ron").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_config_is_valid() {
+        assert_eq!(WorldConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn configs_that_break_the_chunks_are_rejected() {
+        let with = |change: fn(&mut WorldConfig)| {
+            let mut config = WorldConfig::default();
+            change(&mut config);
+            config
+        };
+        let invalid = [
+            with(|config| config.chunk_width = 0),
+            with(|config| config.block_size = 0),
+            with(|config| config.chunk_width = 7), //960 blocks aren't whole chunks of 7
+            with(|config| config.chunks_to_load = 1000),
+            with(|config| config.character_chunks_to_load = 1000),
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+}
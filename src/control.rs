@@ -1,47 +1,56 @@
 use bevy::{
     app::{Plugin, Startup, Update},
     input::ButtonInput,
-    prelude::{Commands, KeyCode, Res, ResMut, Resource},
+    prelude::{in_state, Commands, IntoSystemConfigs, KeyCode, Res, ResMut, Resource},
     time::Time,
 };
 
-use crate::CAMERA_REGULAR_SPEED;
+use crate::{game::AppState, game_world::GameWorld};
 
 #[derive(Resource)]
 pub struct MapControlOffset(pub f32, pub f32);
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct CharacterControlInput {
     pub x: f32,
     pub y: f32,
+    pub attack: bool,
 }
 
 pub struct ControlPlugin;
 
 impl Plugin for ControlPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, startup)
-            .add_systems(Update, (map_movement_input, character_movement_input));
+        app.add_systems(Startup, startup).add_systems(
+            Update,
+            (map_movement_input, character_movement_input).run_if(in_state(AppState::Playing)),
+        );
     }
 }
 
 fn startup(mut commands: Commands) {
     commands.insert_resource(MapControlOffset(0., 0.));
-    commands.insert_resource(CharacterControlInput { x: 0., y: 0. });
+    commands.insert_resource(CharacterControlInput {
+        x: 0.,
+        y: 0.,
+        attack: false,
+    });
 }
 
 fn map_movement_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut control_offset: ResMut<MapControlOffset>,
     time: Res<Time>,
+    game_world: Res<GameWorld>,
 ) {
+    let camera_regular_speed = game_world.config.camera_regular_speed() as i32;
     let xdelta = (((keys.pressed(KeyCode::ArrowRight) as i32)
         - (keys.pressed(KeyCode::ArrowLeft) as i32))
-        * (CAMERA_REGULAR_SPEED as i32)) as f32
+        * camera_regular_speed) as f32
         * time.delta_seconds();
     let ydelta = (((keys.pressed(KeyCode::ArrowUp) as i32)
         - (keys.pressed(KeyCode::ArrowDown) as i32))
-        * (CAMERA_REGULAR_SPEED as i32)) as f32
+        * camera_regular_speed) as f32
         * time.delta_seconds();
 
     control_offset.0 = xdelta;
@@ -55,4 +64,5 @@ fn character_movement_input(
     control_input.x =
         (-(keys.pressed(KeyCode::KeyA) as i8) + (keys.pressed(KeyCode::KeyD) as i8)) as f32;
     control_input.y = (keys.just_pressed(KeyCode::Space) as i8) as f32;
+    control_input.attack = keys.just_pressed(KeyCode::KeyF);
 }
@@ -0,0 +1,263 @@
+use bevy::math::Vec2;
+
+use crate::config::WorldConfig;
+
+/// Column and row of a block. Rows start at zero at the bottom of the world; columns wrap
+/// around the world width, so two positions a whole turn apart are the same block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Index of a chunk, from zero to `chunk_count`. Chunk `n` holds the columns from
+/// `n * chunk_width` on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ChunkPos(pub i32);
+
+/// Position in pixels in the game world, the space transforms live in. The world starts at
+/// the origin and repeats every `world_width_in_pixels` along x.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WorldPos(pub Vec2);
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32) -> Self {
+        BlockPos { x, y }
+    }
+
+    /// Same block with the column brought into the world width.
+    pub fn wrap(self, config: &WorldConfig) -> Self {
+        BlockPos::new(self.x.rem_euclid(config.world_width() as i32), self.y)
+    }
+
+    pub fn offset(self, x: i32, y: i32) -> Self {
+        BlockPos::new(self.x + x, self.y + y)
+    }
+
+    pub fn up(self) -> Self {
+        self.offset(0, 1)
+    }
+
+    /// Whether the row is inside the world. Columns always are, they wrap around.
+    pub fn is_inside(self, config: &WorldConfig) -> bool {
+        self.y >= 0 && self.y < config.world_height as i32
+    }
+
+    pub fn chunk(self, config: &WorldConfig) -> ChunkPos {
+        ChunkPos(self.wrap(config).x / config.chunk_width as i32)
+    }
+
+    /// Column of the block inside its chunk.
+    pub fn col_in_chunk(self, config: &WorldConfig) -> i32 {
+        self.wrap(config).x % config.chunk_width as i32
+    }
+
+    /// Center of the block relative to its chunk.
+    pub fn local_position(self, config: &WorldConfig) -> Vec2 {
+        let block_size = config.block_size as f32;
+        Vec2::new(
+            self.col_in_chunk(config) as f32 * block_size,
+            self.y as f32 * block_size,
+        )
+    }
+
+    /// Center of the block in the game world, on the turn the world starts at.
+    pub fn to_world(self, config: &WorldConfig) -> WorldPos {
+        let block_size = config.block_size as f32;
+        let column = self.wrap(config).x - origin_column(config);
+        WorldPos(Vec2::new(
+            column as f32 * block_size,
+            self.y as f32 * block_size + config.world_bottom_offset_in_pixels() as f32,
+        ))
+    }
+
+    /// Block under a position. Blocks are centered on their position, so each one covers
+    /// half a block on every side of it.
+    pub fn from_world(position: WorldPos, config: &WorldConfig) -> Self {
+        let block_size = config.block_size as f32;
+        let bottom = config.world_bottom_offset_in_pixels() as f32;
+        BlockPos::new(
+            ((position.0.x + block_size / 2.) / block_size).floor() as i32 + origin_column(config),
+            ((position.0.y - bottom + block_size / 2.) / block_size).floor() as i32,
+        )
+        .wrap(config)
+    }
+}
+
+impl ChunkPos {
+    /// Chunk shown at a slot. Slots sit side by side along x, slot 0 starting at the origin,
+    /// and every `chunk_count` slots the world starts over.
+    pub fn from_slot(slot: i32, config: &WorldConfig) -> Self {
+        ChunkPos(
+            (config.chunk_initial_offset() as i32 + slot).rem_euclid(config.chunk_count() as i32),
+        )
+    }
+
+    /// Neighbour chunk `offset` chunks away, wrapping around the world.
+    pub fn offset(self, offset: i32, config: &WorldConfig) -> Self {
+        ChunkPos((self.0 + offset).rem_euclid(config.chunk_count() as i32))
+    }
+
+    /// Bottom left block of the chunk.
+    pub fn first_block(self, config: &WorldConfig) -> BlockPos {
+        BlockPos::new(self.0 * config.chunk_width as i32, 0)
+    }
+
+    pub fn contains(self, block: BlockPos, config: &WorldConfig) -> bool {
+        block.chunk(config) == self
+    }
+}
+
+impl WorldPos {
+    pub const ZERO: WorldPos = WorldPos(Vec2::ZERO);
+
+    /// Slot of the chunk under the position.
+    pub fn chunk_slot(self, config: &WorldConfig) -> i32 {
+        let half_block = config.block_size as f32 / 2.;
+        ((self.0.x + half_block) / config.chunk_width_in_pixels() as f32).floor() as i32
+    }
+
+    /// Left edge of a chunk slot, where the chunk entity is placed.
+    pub fn from_slot(slot: i32, config: &WorldConfig) -> Self {
+        WorldPos(Vec2::new(
+            (slot * config.chunk_width_in_pixels() as i32) as f32,
+            config.world_bottom_offset_in_pixels() as f32,
+        ))
+    }
+
+    /// Center of the block under the position, on the same turn around the world.
+    pub fn snap_to_block(self, config: &WorldConfig) -> Self {
+        let block_size = config.block_size as f32;
+        let bottom = config.world_bottom_offset_in_pixels() as f32;
+        let snap = |value: f32| ((value + block_size / 2.) / block_size).floor() * block_size;
+        WorldPos(Vec2::new(snap(self.0.x), snap(self.0.y - bottom) + bottom))
+    }
+}
+
+//Column of the block centered on the origin
+fn origin_column(config: &WorldConfig) -> i32 {
+    (config.chunk_initial_offset() * config.chunk_width) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WorldConfig {
+        WorldConfig::default()
+    }
+
+    //Positions spread over a few turns around the world, on both sides of the origin. Steps are
+    //quarter pixels so the math stays exact and block borders get hit too
+    fn world_positions(config: &WorldConfig) -> impl Iterator<Item = WorldPos> {
+        let turn = config.world_width_in_pixels() as f32;
+        let height = (config.world_height * config.block_size) as f32;
+        let bottom = config.world_bottom_offset_in_pixels() as f32;
+        (0..4000).map(move |i| {
+            let x = (i as f32 * 7.25).rem_euclid(3. * turn) - 1.5 * turn;
+            let y = bottom + (i as f32 * 3.25).rem_euclid(height) - 8.;
+            WorldPos(Vec2::new(x, y))
+        })
+    }
+
+    #[test]
+    fn every_block_round_trips_through_the_world() {
+        let config = config();
+        for x in 0..config.world_width() as i32 {
+            for y in [
+                0,
+                1,
+                config.world_height as i32 / 2,
+                config.world_height as i32 - 1,
+            ] {
+                let block = BlockPos::new(x, y);
+                assert_eq!(
+                    BlockPos::from_world(block.to_world(&config), &config),
+                    block
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn positions_map_to_the_block_they_are_in() {
+        let config = config();
+        let half_block = config.block_size as f32 / 2.;
+        for position in world_positions(&config) {
+            let block = BlockPos::from_world(position, &config);
+            let center = position.snap_to_block(&config);
+            assert_eq!(BlockPos::from_world(center, &config), block);
+            let distance = (position.0 - center.0).abs();
+            assert!(
+                distance.x <= half_block && distance.y <= half_block,
+                "{:?}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn a_whole_turn_lands_on_the_same_block() {
+        let config = config();
+        let turn = Vec2::new(config.world_width_in_pixels() as f32, 0.);
+        for position in world_positions(&config) {
+            let block = BlockPos::from_world(position, &config);
+            assert_eq!(
+                BlockPos::from_world(WorldPos(position.0 + turn), &config),
+                block
+            );
+            assert_eq!(
+                BlockPos::from_world(WorldPos(position.0 - turn), &config),
+                block
+            );
+        }
+    }
+
+    #[test]
+    fn blocks_belong_to_the_chunk_of_their_slot() {
+        let config = config();
+        for position in world_positions(&config) {
+            let block = BlockPos::from_world(position, &config);
+            let chunk = ChunkPos::from_slot(position.chunk_slot(&config), &config);
+            assert_eq!(block.chunk(&config), chunk, "{:?}", position);
+            assert!(chunk.contains(block, &config));
+
+            //The block sits at its local position inside the chunk placed at the slot
+            let chunk_origin = WorldPos::from_slot(position.chunk_slot(&config), &config);
+            assert_eq!(
+                chunk_origin.0 + block.local_position(&config),
+                position.snap_to_block(&config).0
+            );
+        }
+    }
+
+    #[test]
+    fn wrapping_keeps_columns_inside_the_world() {
+        let config = config();
+        let width = config.world_width() as i32;
+        for x in -3 * width..3 * width {
+            let block = BlockPos::new(x, 0).wrap(&config);
+            assert!((0..width).contains(&block.x));
+            assert_eq!(block.x, x.rem_euclid(width));
+            assert!((0..config.chunk_width as i32).contains(&block.col_in_chunk(&config)));
+            assert!((0..config.chunk_count() as i32).contains(&block.chunk(&config).0));
+        }
+    }
+
+    #[test]
+    fn chunk_offsets_wrap_around_the_world() {
+        let config = config();
+        let count = config.chunk_count() as i32;
+        assert_eq!(ChunkPos(0).offset(-1, &config), ChunkPos(count - 1));
+        assert_eq!(ChunkPos(count - 1).offset(1, &config), ChunkPos(0));
+        assert_eq!(
+            ChunkPos::from_slot(count, &config),
+            ChunkPos::from_slot(0, &config)
+        );
+        for chunk in 0..count {
+            let first = ChunkPos(chunk).first_block(&config);
+            assert_eq!(first.chunk(&config), ChunkPos(chunk));
+            assert_eq!(first.col_in_chunk(&config), 0);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::CursorWorldPosition,
+    character::{Character, CoinPouch},
+    coordinates::{BlockPos, WorldPos},
+    game::AppState,
+    game_world::GameWorld,
+    map::{Block, Chunk, ChunkEditedEvent, ChunkState, SolidBlock},
+};
+
+const DIG_REACH_IN_BLOCKS: f32 = 4.;
+
+pub struct DiggingPlugin;
+
+impl Plugin for DiggingPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.register_type::<BlockInventory>().add_systems(
+            Update,
+            (dig, place_block).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Blocks the character has dug out and can place back.
+#[derive(Component, Reflect, Default)]
+pub struct BlockInventory {
+    pub earth: u32,
+    pub stone: u32,
+}
+
+impl BlockInventory {
+    fn take(&mut self) -> Option<SolidBlock> {
+        if self.earth > 0 {
+            self.earth -= 1;
+            Some(SolidBlock::Earth)
+        } else if self.stone > 0 {
+            self.stone -= 1;
+            Some(SolidBlock::Stone)
+        } else {
+            None
+        }
+    }
+}
+
+fn dig(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cursor_position: Res<CursorWorldPosition>,
+    mut char_query: Query<(&Character, &Transform, &mut BlockInventory, &mut CoinPouch)>,
+    chunks: Query<&Chunk>,
+    mut game_world: ResMut<GameWorld>,
+    mut commands: Commands,
+) {
+    let Ok((character, char_transform, mut inventory, mut coin_pouch)) =
+        char_query.get_single_mut()
+    else {
+        return;
+    };
+    let block_size = game_world.config.block_size as f32;
+    let char_position = char_transform.translation.truncate();
+
+    let target = if mouse.just_pressed(MouseButton::Left) {
+        cursor_position.0
+    } else if keys.just_pressed(KeyCode::KeyE) {
+        let direction = if character.is_looking_left() { -1. } else { 1. };
+        Some(char_position + Vec2::new(direction * block_size, 0.))
+    } else {
+        None
+    };
+
+    let Some(target) = target.filter(|t| t.distance(char_position) <= reach(&game_world)) else {
+        return;
+    };
+    let Some((pos, _)) = get_block_coordinates(target, &chunks, &game_world) else {
+        return;
+    };
+
+    match game_world.get_block(pos) {
+        Block::Air => return,
+        Block::Solid(SolidBlock::Ore(item_type)) => coin_pouch.0 += item_type.get_coins(),
+        Block::Solid(SolidBlock::Stone) => inventory.stone += 1,
+        Block::Solid(SolidBlock::Earth | SolidBlock::Surface(_)) => inventory.earth += 1,
+    }
+
+    game_world.set_edit(pos, Block::Air);
+    trigger_chunk_edits(pos, &game_world, &mut commands);
+}
+
+fn place_block(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor_position: Res<CursorWorldPosition>,
+    mut char_query: Query<(&Transform, &mut BlockInventory), With<Character>>,
+    chunks: Query<&Chunk>,
+    mut game_world: ResMut<GameWorld>,
+    mut commands: Commands,
+) {
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Ok((char_transform, mut inventory)) = char_query.get_single_mut() else {
+        return;
+    };
+    let char_position = char_transform.translation.truncate();
+    let Some(target) = cursor_position
+        .0
+        .filter(|t| t.distance(char_position) <= reach(&game_world))
+    else {
+        return;
+    };
+    let Some((pos, block_center)) = get_block_coordinates(target, &chunks, &game_world) else {
+        return;
+    };
+
+    //The block must not end up inside the character
+    let config = &game_world.config;
+    let overlap = Vec2::new(
+        (config.block_size + config.character_size() * 2 / 3) as f32 / 2.,
+        (config.block_size + config.character_size()) as f32 / 2.,
+    );
+    let distance = (block_center - char_position).abs();
+    if game_world.get_block(pos) != Block::Air || (distance.x < overlap.x && distance.y < overlap.y)
+    {
+        return;
+    }
+
+    if let Some(block) = inventory.take() {
+        game_world.set_edit(pos, Block::Solid(block));
+        trigger_chunk_edits(pos, &game_world, &mut commands);
+    }
+}
+
+fn reach(game_world: &GameWorld) -> f32 {
+    DIG_REACH_IN_BLOCKS * game_world.config.block_size as f32
+}
+
+//Blocks on the border of a chunk change the autotiles of the neighbour chunk as well
+fn trigger_chunk_edits(pos: BlockPos, game_world: &GameWorld, commands: &mut Commands) {
+    let config = &game_world.config;
+    let chunk_index = pos.chunk(config);
+    commands.trigger(ChunkEditedEvent { chunk_index });
+
+    let col_x = pos.col_in_chunk(config);
+    if col_x == 0 {
+        commands.trigger(ChunkEditedEvent {
+            chunk_index: chunk_index.offset(-1, config),
+        });
+    } else if col_x == config.chunk_width as i32 - 1 {
+        commands.trigger(ChunkEditedEvent {
+            chunk_index: chunk_index.offset(1, config),
+        });
+    }
+}
+
+/// Block under a position in pixels, if it is in the world and its chunk is loaded, along
+/// with the position of the block's center.
+fn get_block_coordinates(
+    position: Vec2,
+    chunks: &Query<&Chunk>,
+    game_world: &GameWorld,
+) -> Option<(BlockPos, Vec2)> {
+    let config = &game_world.config;
+    let position = WorldPos(position);
+    let pos = BlockPos::from_world(position, config);
+    let chunk_index = pos.chunk(config);
+    let is_loaded = chunks
+        .iter()
+        .any(|chunk| chunk.index == chunk_index && chunk.state == ChunkState::ChunkLoaded);
+    if !is_loaded || !pos.is_inside(config) {
+        return None;
+    }
+    Some((pos, position.snap_to_block(config).0))
+}
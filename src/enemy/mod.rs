@@ -1,58 +1,147 @@
 use bevy::{
     app::{Plugin, Startup, Update},
-    asset::{AssetServer, Assets},
     prelude::*,
-    reflect::Reflect,
-    sprite::TextureAtlasLayout,
     transform::components::Transform,
+    utils::HashSet,
 };
-use slime::{animate_slime, slime_movement, spawn_slime};
+use bevy_rand::prelude::*;
+use slime::{animate_slime, load_textures, slime_movement, spawn_slime, SlimeTextures};
+pub use slime::{Slime, SlimeKind};
 
 mod slime;
 
-use crate::{GameWorld, BLOCK_SIZE, WORLD_BOTTOM_OFFSET_IN_PIXELS, WORLD_CENTER_COL};
-
-#[derive(Component, Reflect)]
-pub struct HealthPoints {
-    pub max_full_hearts: u8,
-    pub current: u8,
-}
-
-impl HealthPoints {
-    fn full(hearts: u8) -> Self {
-        HealthPoints {
-            max_full_hearts: hearts,
-            current: hearts * 2,
-        }
-    }
-}
+use crate::{
+    coordinates::{BlockPos, WorldPos},
+    game::{AppState, DespawnWorld, GameStartupSet, SpawnWorld},
+    game_world::GameWorld,
+    health::Died,
+    map::{Chunk, ChunkUnloadingEvent, DespawnChunksSet, NewChunkEvent},
+    utils::rand_weighted_item,
+};
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.register_type::<HealthPoints>()
-            .add_systems(Startup, startup)
-            .add_systems(Update, (slime_movement, animate_slime));
+        app.init_resource::<SpawnedStructureEnemies>()
+            .observe(on_new_chunk)
+            .observe(on_chunk_unloading)
+            .observe(on_enemy_died)
+            .add_systems(Startup, load_textures)
+            .add_systems(SpawnWorld, startup.in_set(GameStartupSet::Enemies))
+            .add_systems(DespawnWorld, despawn_enemies.after(DespawnChunksSet))
+            .add_systems(
+                Update,
+                (slime_movement, animate_slime).run_if(in_state(AppState::Playing)),
+            );
     }
 }
 
+//Structure enemies that are out in the world or were killed. The killed ones stay dead, the
+//others come back home whenever the chunk they roam in unloads
+#[derive(Resource, Default)]
+struct SpawnedStructureEnemies(HashSet<BlockPos>);
+
+//Block of its structure the enemy spawned in
+#[derive(Component)]
+struct StructureEnemy(BlockPos);
+
 fn startup(
-    commands: Commands,
-    asset_server: Res<AssetServer>,
+    mut commands: Commands,
     game_world: Res<GameWorld>,
-    texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    slime_textures: Res<SlimeTextures>,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
 ) {
+    let mut rng = global_entropy.fork_rng();
+    let config = &game_world.config;
+    let spawn_point = game_world.spawn_point();
+    let spawn_table = game_world.get_biome(spawn_point).enemy_spawn_table();
     spawn_slime(
-        commands,
-        asset_server,
-        texture_atlases,
-        Transform::from_xyz(
-            (BLOCK_SIZE as f32) * 0.5,
-            (((game_world.get_height_in_blocks(WORLD_CENTER_COL) as usize + 10) * BLOCK_SIZE)
-                as f32)
-                + WORLD_BOTTOM_OFFSET_IN_PIXELS as f32,
-            4.0,
-        ),
-    )
+        &mut commands,
+        &slime_textures,
+        Transform::from_translation(spawn_point.to_world(config).0.extend(4.0)),
+        (config.block_size * 2) as f32,
+        rand_weighted_item(spawn_table, &mut rng),
+    );
+}
+
+//Structure enemies show up again once their chunk loads in the new world
+fn despawn_enemies(
+    query: Query<Entity, With<Slime>>,
+    mut spawned: ResMut<SpawnedStructureEnemies>,
+    mut commands: Commands,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawned.0.clear();
+}
+
+//Enemies wait in their structure until its chunk is loaded, so they have ground to stand on
+fn on_new_chunk(
+    trigger: Trigger<NewChunkEvent>,
+    chunks: Query<(&Chunk, &Transform)>,
+    game_world: Res<GameWorld>,
+    slime_textures: Res<SlimeTextures>,
+    mut spawned: ResMut<SpawnedStructureEnemies>,
+    mut commands: Commands,
+) {
+    let Ok((chunk, chunk_transform)) = chunks.get(trigger.event().chunk) else {
+        return;
+    };
+    let config = &game_world.config;
+    let slime_size = (config.block_size * 2) as f32;
+    let enemies = game_world
+        .structures()
+        .iter()
+        .flat_map(|structure| &structure.enemies)
+        .filter(|(pos, _)| chunk.index.contains(*pos, config));
+    for (pos, kind) in enemies {
+        if !spawned.0.insert(*pos) {
+            continue;
+        }
+        //Standing on the bottom of the block, the slime is two blocks tall
+        let position = chunk_transform.translation.truncate()
+            + pos.local_position(config)
+            + Vec2::new(0., slime_size / 4.);
+        let entity = spawn_slime(
+            &mut commands,
+            &slime_textures,
+            Transform::from_translation(position.extend(4.0)),
+            slime_size,
+            *kind,
+        );
+        commands.entity(entity).insert(StructureEnemy(*pos));
+    }
+}
+
+//The ground under the enemies goes with the chunk, so they go too. Structure enemies are sent
+//back to their structure, the others are gone for good
+fn on_chunk_unloading(
+    trigger: Trigger<ChunkUnloadingEvent>,
+    chunks: Query<&Transform, With<Chunk>>,
+    enemies: Query<(Entity, &Transform, Option<&StructureEnemy>), With<Slime>>,
+    game_world: Res<GameWorld>,
+    mut spawned: ResMut<SpawnedStructureEnemies>,
+    mut commands: Commands,
+) {
+    let Ok(chunk_transform) = chunks.get(trigger.event().chunk) else {
+        return;
+    };
+    let config = &game_world.config;
+    let chunk_slot = WorldPos(chunk_transform.translation.truncate()).chunk_slot(config);
+    for (entity, transform, structure_enemy) in enemies.iter() {
+        if WorldPos(transform.translation.truncate()).chunk_slot(config) == chunk_slot {
+            if let Some(StructureEnemy(home)) = structure_enemy {
+                spawned.0.remove(home);
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn on_enemy_died(trigger: Trigger<Died>, slimes: Query<(), With<Slime>>, mut commands: Commands) {
+    if slimes.contains(trigger.entity()) {
+        commands.entity(trigger.entity()).despawn_recursive();
+    }
 }
@@ -1,5 +1,5 @@
 use bevy::{
-    asset::{AssetServer, Assets},
+    asset::{AssetServer, Assets, Handle},
     math::{UVec2, Vec2},
     prelude::*,
     sprite::{Sprite, SpriteBundle, TextureAtlas, TextureAtlasLayout},
@@ -8,11 +8,15 @@ use bevy::{
 };
 use bevy_rapier2d::prelude::*;
 
-use crate::{BLOCK_SIZE, GRAVITY, PIXEL_PERFECT_LAYERS};
-
-use super::HealthPoints;
+use crate::{
+    combat::Knockback,
+    health::{HealthPoints, HitInvulnerability, Resistances},
+    loading::RequiredAssets,
+    wraparound::WorldWrap,
+    GRAVITY, PIXEL_PERFECT_LAYERS,
+};
 
-const SLIME_SIZE: f32 = (BLOCK_SIZE * 2) as f32;
+const INVULNERABILITY_AFTER_HIT: f32 = 0.3;
 
 #[derive(Debug, Default, PartialEq)]
 enum SlimeState {
@@ -32,6 +36,32 @@ impl SlimeState {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlimeKind {
+    Green,
+    Red,
+}
+
+impl SlimeKind {
+    fn get_texture_path(&self) -> &'static str {
+        match self {
+            SlimeKind::Green => "enemies/green-slime-spritesheet.png",
+            SlimeKind::Red => "enemies/red-slime-spritesheet.png",
+        }
+    }
+
+    //Red slimes shrug off part of every attack, stomping them works better
+    fn resistances(&self) -> Resistances {
+        match self {
+            SlimeKind::Green => Resistances::default(),
+            SlimeKind::Red => Resistances {
+                attack: 1,
+                ..default()
+            },
+        }
+    }
+}
+
 #[derive(Component, Deref, DerefMut)]
 pub struct AnimationTimer(Timer);
 
@@ -47,77 +77,120 @@ pub struct Direction {
     x: f32,
 }
 
-pub fn spawn_slime(
+//Every slime falls on its own
+#[derive(Component, Default)]
+pub struct VerticalVelocity(f32);
+
+/// Spritesheets of every slime kind, loaded up front so slimes never pop in untextured.
+#[derive(Resource)]
+pub struct SlimeTextures {
+    green: Handle<Image>,
+    red: Handle<Image>,
+    atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+impl SlimeTextures {
+    fn get(&self, kind: SlimeKind) -> Handle<Image> {
+        match kind {
+            SlimeKind::Green => self.green.clone(),
+            SlimeKind::Red => self.red.clone(),
+        }
+    }
+}
+
+pub fn load_textures(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    position: Transform,
+    mut required_assets: ResMut<RequiredAssets>,
 ) {
     let atlas_layout =
         TextureAtlasLayout::from_grid(UVec2::new(16, 16), 6, 1, None, Some(UVec2::new(16, 16)));
-    let atlas_layout_handle = texture_atlases.add(atlas_layout);
-    let texture = asset_server.load("enemies/red-slime-spritesheet.png");
-
-    commands.spawn((
-        Slime {
-            movement_speed: 80.0,
-            looking_left: false,
-            state: SlimeState::Idle,
-        },
-        SpriteBundle {
-            texture,
-            transform: position,
-            sprite: Sprite {
-                custom_size: Option::Some(Vec2::new(SLIME_SIZE, SLIME_SIZE)),
+    let textures = SlimeTextures {
+        green: asset_server.load(SlimeKind::Green.get_texture_path()),
+        red: asset_server.load(SlimeKind::Red.get_texture_path()),
+        atlas_layout: texture_atlases.add(atlas_layout),
+    };
+    required_assets.track(textures.green.clone());
+    required_assets.track(textures.red.clone());
+    commands.insert_resource(textures);
+}
+
+pub fn spawn_slime(
+    commands: &mut Commands,
+    textures: &SlimeTextures,
+    position: Transform,
+    slime_size: f32,
+    kind: SlimeKind,
+) -> Entity {
+    commands
+        .spawn((
+            Slime {
+                movement_speed: 80.0,
+                looking_left: false,
+                state: SlimeState::Idle,
+            },
+            SpriteBundle {
+                texture: textures.get(kind),
+                transform: position,
+                sprite: Sprite {
+                    custom_size: Option::Some(Vec2::new(slime_size, slime_size)),
+                    ..default()
+                },
+                ..default()
+            },
+            TextureAtlas {
+                layout: textures.atlas_layout.clone(),
+                index: 0,
+            },
+            RigidBody::KinematicPositionBased,
+            Collider::capsule_y(slime_size / 16.0, slime_size / 2.0),
+            KinematicCharacterController {
+                custom_shape: Option::Some((
+                    Collider::cuboid(slime_size / 3.0, slime_size / 2.0),
+                    Vec2::new(0., slime_size * 0.04),
+                    0.,
+                )),
+                offset: CharacterLength::Absolute(0.1),
+                snap_to_ground: Option::Some(CharacterLength::Absolute(0.1)),
                 ..default()
             },
-            ..default()
-        },
-        TextureAtlas {
-            layout: atlas_layout_handle,
-            index: 0,
-            ..Default::default()
-        },
-        RigidBody::KinematicPositionBased,
-        Collider::capsule_y(SLIME_SIZE / 16.0, SLIME_SIZE / 2.0),
-        KinematicCharacterController {
-            custom_shape: Option::Some((
-                Collider::cuboid(SLIME_SIZE / 3.0, SLIME_SIZE / 2.0),
-                Vec2::new(0., SLIME_SIZE * 0.04),
-                0.,
-            )),
-            offset: CharacterLength::Absolute(0.1),
-            snap_to_ground: Option::Some(CharacterLength::Absolute(0.1)),
-            ..default()
-        },
-        AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-        HealthPoints::full(1),
-        Direction { x: 1.0 },
-        PIXEL_PERFECT_LAYERS,
-    ));
+            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+            HealthPoints::full(1),
+            kind.resistances(),
+            HitInvulnerability(INVULNERABILITY_AFTER_HIT),
+            Direction { x: 1.0 },
+            VerticalVelocity::default(),
+            WorldWrap,
+            PIXEL_PERFECT_LAYERS,
+        ))
+        .id()
 }
 
 pub fn slime_movement(
     mut query: Query<(
         &mut Slime,
         &mut Direction,
+        &mut VerticalVelocity,
         &mut KinematicCharacterController,
         Option<&KinematicCharacterControllerOutput>,
         &mut Sprite,
         &mut TextureAtlas,
+        Option<&Knockback>,
     )>,
     time: Res<Time>,
-    mut vertical_movement: Local<f32>,
 ) {
     let delta_time = time.delta_seconds();
 
     for (
         mut enemy,
         mut direction,
+        mut vertical_velocity,
         mut enemy_controller,
         enemy_controller_output,
         mut sprite,
         mut atlas,
+        knockback,
     ) in query.iter_mut()
     {
         let x = direction.x;
@@ -129,14 +202,14 @@ pub fn slime_movement(
         }
 
         if enemy_controller_output.map(|o| o.grounded).unwrap_or(false) {
-            *vertical_movement = 0.0;
+            vertical_velocity.0 = 0.0;
         }
 
-        *vertical_movement += GRAVITY * delta_time * enemy_controller.custom_mass.unwrap_or(1.0);
+        vertical_velocity.0 += GRAVITY * delta_time * enemy_controller.custom_mass.unwrap_or(1.0);
 
-        move_delta.y = *vertical_movement;
+        move_delta.y = vertical_velocity.0;
 
-        let next_state = if *vertical_movement < -0.4 {
+        let next_state = if vertical_velocity.0 < -0.4 {
             SlimeState::Falling
         } else if move_delta.x.abs() > f32::EPSILON {
             SlimeState::Walking
@@ -175,7 +248,9 @@ pub fn slime_movement(
             }
         }
 
-        enemy_controller.translation = Some(move_delta * enemy.movement_speed as f32 * delta_time);
+        let knockback = knockback.map_or(Vec2::ZERO, |knockback| knockback.0);
+        enemy_controller.translation =
+            Some((move_delta * enemy.movement_speed + knockback) * delta_time);
     }
 }
 
@@ -0,0 +1,27 @@
+use bevy_rand::prelude::*;
+use rand::SeedableRng;
+
+use crate::{
+    config::WorldConfig,
+    game_world::GameWorld,
+    pickables::{generate_pickables, Pickable},
+    seed::WorldSeed,
+};
+
+/// World the tests and the benchmarks run on.
+pub fn test_world() -> GameWorld {
+    world_for(WorldSeed(42))
+}
+
+/// World generated from the seed with the default config.
+pub fn world_for(seed: WorldSeed) -> GameWorld {
+    GameWorld::generate(&seed, WorldConfig::default())
+}
+
+/// Pickables the game lays out for the seed. The pickables are the first to fork the global
+/// entropy when the world spawns, so a fresh global entropy seeded the same way gives their
+/// stream back without running the app.
+pub fn seeded_pickables(seed: &WorldSeed, game_world: &GameWorld) -> Vec<Pickable> {
+    let mut global_entropy = GlobalEntropy::<WyRand>::from_seed(seed.entropy_seed());
+    generate_pickables(game_world, &mut global_entropy.fork_rng())
+}
@@ -1,17 +1,98 @@
 use bevy::{
-    app::{Plugin, PluginGroup, PluginGroupBuilder},
-    prelude::Resource,
+    app::{Plugin, PluginGroup, PluginGroupBuilder, Update},
+    ecs::schedule::ScheduleLabel,
+    input::ButtonInput,
+    prelude::{
+        in_state, AppExtStates, ComputedStates, IntoSystemConfigs, IntoSystemSetConfigs, KeyCode,
+        NextState, OnEnter, OnExit, Res, ResMut, Resource, States, SystemSet, World,
+    },
+    time::{Time, Virtual},
 };
+use bevy_rand::prelude::*;
+use bevy_rapier2d::plugin::RapierConfiguration;
 
 use crate::{
-    camera::CameraPlugin, character::CharacterPlugin, control::ControlPlugin, enemy::EnemyPlugin,
-    map::MapPlugin, pickables::PickablesPlugin, ui::UIPlugin,
+    background::BackgroundPlugin, camera::CameraPlugin, character::CharacterPlugin,
+    combat::CombatPlugin, control::ControlPlugin, digging::DiggingPlugin, enemy::EnemyPlugin,
+    game_over::GameOverPlugin, game_world::GameWorld, health::HealthPlugin, loading::LoadingPlugin,
+    map::MapPlugin, menu::MenuPlugin, pickables::PickablesPlugin, seed::WorldSeed,
+    theme::ThemePlugin, ui::UIPlugin, wraparound::WrapAroundPlugin,
 };
 
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+pub const FIRST_DAY: i32 = 1; //day count of a new world
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
-    fn build(&self, app: &mut bevy::prelude::App) {}
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<TimeOfDay>()
+            .init_state::<AppState>()
+            .add_computed_state::<InGame>()
+            .enable_state_scoped_entities::<AppState>()
+            .enable_state_scoped_entities::<InGame>()
+            .init_schedule(SpawnWorld)
+            .init_schedule(DespawnWorld)
+            .configure_sets(
+                SpawnWorld,
+                (GameStartupSet::Pickables, GameStartupSet::Enemies).chain(),
+            )
+            .add_systems(OnEnter(InGame), spawn_world)
+            .add_systems(OnExit(InGame), despawn_world)
+            .add_systems(DespawnWorld, reset_world)
+            .add_systems(OnEnter(AppState::Paused), pause_world)
+            .add_systems(OnExit(AppState::Paused), resume_world)
+            .add_systems(
+                Update,
+                (pause, advance_time_of_day).run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Screens the game goes through. Gameplay systems only run while `Playing`.
+/// The game starts `Loading` and only leaves it once every `RequiredAssets` handle is loaded.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Loading,
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// While the world exists: from leaving the menu until going back to it.
+/// `SpawnWorld` runs when it starts and `DespawnWorld` when it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = AppState;
+
+    fn compute(sources: AppState) -> Option<Self> {
+        matches!(
+            sources,
+            AppState::Playing | AppState::Paused | AppState::GameOver
+        )
+        .then_some(InGame)
+    }
+}
+
+/// Spawns everything that lives in the world: chunks, pickables, enemies and the character.
+/// Runs when the game enters `InGame` and again on every restart, after `DespawnWorld`.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpawnWorld;
+
+/// Despawns what `SpawnWorld` spawned and puts the world resources back as they were at startup.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DespawnWorld;
+
+/// `SpawnWorld` systems that fork the global entropy. They run in a fixed order so every
+/// subsystem gets the same random stream for the same seed.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameStartupSet {
+    Pickables,
+    Enemies,
 }
 
 pub struct GamePluginGroupBuilder;
@@ -22,13 +103,92 @@ impl PluginGroup for GamePluginGroupBuilder {
             .add(GamePlugin)
             .add(MapPlugin)
             .add(CameraPlugin)
+            .add(BackgroundPlugin)
+            .add(WrapAroundPlugin)
             .add(ControlPlugin)
             .add(UIPlugin)
+            .add(ThemePlugin)
             .add_after::<MapPlugin, CharacterPlugin>(CharacterPlugin)
             .add_after::<GamePlugin, PickablesPlugin>(PickablesPlugin)
             .add_after::<MapPlugin, EnemyPlugin>(EnemyPlugin)
+            .add_after::<CharacterPlugin, DiggingPlugin>(DiggingPlugin)
+            .add(HealthPlugin)
+            .add_after::<EnemyPlugin, CombatPlugin>(CombatPlugin)
+            .add(GameOverPlugin)
+            .add(MenuPlugin)
+            .add(LoadingPlugin)
     }
 }
 
 #[derive(Resource)]
 pub struct DayCount(pub i32);
+
+/// Fraction of the current day that has gone by: 0 is midnight and 0.5 is noon.
+#[derive(Resource)]
+pub struct TimeOfDay(pub f32);
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        TimeOfDay(0.3) //the first day starts in the morning
+    }
+}
+
+fn advance_time_of_day(
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut day_count: ResMut<DayCount>,
+    time: Res<Time>,
+    game_world: Res<GameWorld>,
+) {
+    time_of_day.0 += time.delta_seconds() / game_world.config.day_duration_in_seconds as f32;
+    if time_of_day.0 >= 1. {
+        time_of_day.0 -= 1.;
+        day_count.0 += 1;
+    }
+}
+
+fn spawn_world(world: &mut World) {
+    world.run_schedule(SpawnWorld);
+}
+
+fn despawn_world(world: &mut World) {
+    world.run_schedule(DespawnWorld);
+}
+
+/// Tears the world down and builds it again from the seed, the same as a fresh launch.
+/// Both schedules run back to back, so no system ever sees the world half gone.
+pub fn restart_world(world: &mut World) {
+    world.run_schedule(DespawnWorld);
+    world.run_schedule(SpawnWorld);
+}
+
+//The edits and the random streams go back to what the seed gives. The generated terrain only
+//depends on the seed and the config, so it is kept
+fn reset_world(
+    seed: Res<WorldSeed>,
+    mut game_world: ResMut<GameWorld>,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut day_count: ResMut<DayCount>,
+) {
+    game_world.clear_edits();
+    global_entropy.reseed(seed.entropy_seed());
+    *time_of_day = TimeOfDay::default();
+    day_count.0 = FIRST_DAY;
+}
+
+fn pause(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(PAUSE_KEY) {
+        next_state.set(AppState::Paused);
+    }
+}
+
+//Gameplay systems already stop outside of `Playing`, this stops the timers and the physics too
+fn pause_world(mut time: ResMut<Time<Virtual>>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.pause();
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_world(mut time: ResMut<Time<Virtual>>, mut rapier_config: ResMut<RapierConfiguration>) {
+    time.unpause();
+    rapier_config.physics_pipeline_active = true;
+}
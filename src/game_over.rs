@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::Character,
+    game::{AppState, DespawnWorld},
+    health::Died,
+};
+
+const GAME_OVER_DELAY: f32 = 1.5; //seconds the death animation plays before the screen shows up
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.observe(on_character_died)
+            .add_systems(
+                Update,
+                count_down.run_if(
+                    in_state(AppState::Playing).and_then(resource_exists::<DeathCountdown>),
+                ),
+            )
+            .add_systems(DespawnWorld, reset_countdown);
+    }
+}
+
+//Time left before the game is over
+#[derive(Resource)]
+struct DeathCountdown(Timer);
+
+fn on_character_died(
+    trigger: Trigger<Died>,
+    characters: Query<(), With<Character>>,
+    mut commands: Commands,
+) {
+    if characters.contains(trigger.entity()) {
+        commands.insert_resource(DeathCountdown(Timer::from_seconds(
+            GAME_OVER_DELAY,
+            TimerMode::Once,
+        )));
+    }
+}
+
+fn count_down(
+    mut countdown: ResMut<DeathCountdown>,
+    time: Res<Time>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    if countdown.0.tick(time.delta()).finished() {
+        commands.remove_resource::<DeathCountdown>();
+        next_state.set(AppState::GameOver);
+    }
+}
+
+//Leaving for the menu while the character is dying
+fn reset_countdown(mut commands: Commands) {
+    commands.remove_resource::<DeathCountdown>();
+}
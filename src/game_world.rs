@@ -1,38 +1,198 @@
-use bevy::prelude::*;
-use noise::utils::NoiseMap;
+use std::sync::Arc;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rand::prelude::WyRand;
+use noise::{utils::*, Fbm, MultiFractal, Perlin, Worley};
 use rand::prelude::*;
 
-use crate::{BLOCK_SIZE, WORLD_WIDTH};
+use crate::{
+    biome::Biome,
+    config::WorldConfig,
+    coordinates::{BlockPos, ChunkPos, WorldPos},
+    map::{Block, SolidBlock},
+    pickables::PickableItemType,
+    seed::WorldSeed,
+    structures::{place_structures, stamp_structures, Structure},
+    utils::rand_weighted_item,
+};
+
+const CAVE_THRESHOLD: f64 = 0.3; //density above which a block is carved out
+const CAVE_SURFACE_FALLOFF: f64 = 0.06; //extra density needed per block closer to the surface
+const CAVE_CRUST_DEPTH: f32 = 6.; //blocks under the surface where the falloff applies
+const BEDROCK_HEIGHT: i32 = 2; //rows at the bottom of the world that are never carved
+
+const EARTH_DEPTH: f32 = 8.; //average earth blocks between the surface and the stone
+const EARTH_DEPTH_VARIATION: f32 = 4.; //how much the noise moves the stone line up and down
+const ORE_THRESHOLD: f64 = 0.45; //ore density above which stone becomes an ore vein
+const SPAWN_HEIGHT: i32 = 10; //blocks above the surface things drop in from
+const PROP_SEED_SALT: u64 = 0x9e3779b97f4a7c15; //keeps the prop stream apart from the entropy seed
 
-#[derive(Resource)]
+//Ores from the shallowest to the deepest
+const ORE_LAYERS: [PickableItemType; 4] = [
+    PickableItemType::Gold,
+    PickableItemType::Emmerald,
+    PickableItemType::Gem,
+    PickableItemType::Diammond,
+];
+
+/// Cloning is cheap: the generated terrain is shared, only the player's edits are copied.
+/// Background tasks build chunks from a clone.
+#[derive(Resource, Clone)]
 pub struct GameWorld {
     pub width: i32,
-    pub noise_map: NoiseMap,
+    pub config: WorldConfig, //the only copy of the config, every system reads it from here
+    terrain: Arc<Terrain>,
+    //Player edits per chunk, keyed by wrapped block positions
+    edits: HashMap<ChunkPos, HashMap<BlockPos, Block>>,
+}
+
+//Everything generated from the seed, never changes after generation
+struct Terrain {
+    biome_weights: Vec<[f32; 4]>,
     surface_height: Vec<f32>,
+    cave_map: NoiseMap,
+    earth_depth: Vec<f32>,
+    ore_map: NoiseMap,
+    prop_density: Vec<f32>,
+    prop_seed: u64,
+    structures: Vec<Structure>,
+    //Blocks stamped by the structures, keyed by wrapped block positions
+    structure_blocks: HashMap<BlockPos, Block>,
 }
 
 impl GameWorld {
-    pub fn new(noise_map: NoiseMap, surface_height: Vec<f32>) -> GameWorld {
-        GameWorld {
-            width: WORLD_WIDTH as i32,
-            noise_map,
-            surface_height,
-        }
-    }
+    pub fn generate(seed: &WorldSeed, config: WorldConfig) -> GameWorld {
+        let noise_map = generate_noise_map(seed, &config);
+        let biome_weights = generate_biome_weights_vec(seed, &config);
+        let surface_height = generate_surface_height_vec(&noise_map, &biome_weights, &config);
+        let cave_map = generate_cave_map(seed, &config);
+        let earth_depth = generate_earth_depth_vec(seed, &config);
+        let ore_map = generate_ore_map(seed, &config);
+        let prop_density = generate_prop_density_vec(seed, &config);
 
-    pub fn get_height_in_blocks(&self, x: usize) -> f32 {
-        let height = self.surface_height[x % WORLD_WIDTH].trunc();
-        let left_height = if x > 0 {
-            self.surface_height[x - 1].trunc()
-        } else {
-            self.surface_height[WORLD_WIDTH - 1].trunc()
+        let mut game_world = GameWorld {
+            width: config.world_width() as i32,
+            config,
+            terrain: Arc::new(Terrain {
+                biome_weights,
+                surface_height,
+                cave_map,
+                earth_depth,
+                ore_map,
+                prop_density,
+                prop_seed: seed.0 ^ PROP_SEED_SALT,
+                structures: Vec::new(),
+                structure_blocks: HashMap::default(),
+            }),
+            edits: HashMap::default(),
         };
-        let right_height = if x < (WORLD_WIDTH - 1) {
-            self.surface_height[x + 1].trunc()
-        } else {
-            self.surface_height[0].trunc()
+
+        //Structures are fitted to the generated terrain, so they are stamped in last
+        let structures = place_structures(seed, &game_world);
+        let terrain = Arc::get_mut(&mut game_world.terrain).expect("Terrain is not shared yet.");
+        terrain.structure_blocks = stamp_structures(&structures, &game_world.config);
+        terrain.structures = structures;
+        game_world
+    }
+
+    pub fn get_edit(&self, pos: BlockPos) -> Option<Block> {
+        let pos = pos.wrap(&self.config);
+        self.edits
+            .get(&pos.chunk(&self.config))
+            .and_then(|chunk_edits| chunk_edits.get(&pos))
+            .copied()
+    }
+
+    /// Stores a dug or placed block and returns the chunk that owns it.
+    pub fn set_edit(&mut self, pos: BlockPos, block: Block) -> ChunkPos {
+        let pos = pos.wrap(&self.config);
+        let chunk = pos.chunk(&self.config);
+        self.edits.entry(chunk).or_default().insert(pos, block);
+        chunk
+    }
+
+    /// Drops every dug or placed block, back to the world as it was generated.
+    pub fn clear_edits(&mut self) {
+        self.edits.clear();
+    }
+
+    //Edits first, then the structures, both override the generated terrain
+    fn get_placed_block(&self, pos: BlockPos) -> Option<Block> {
+        self.get_edit(pos).or_else(|| {
+            self.terrain
+                .structure_blocks
+                .get(&pos.wrap(&self.config))
+                .copied()
+        })
+    }
+
+    /// Structures stamped into the world, the spawn shrine first.
+    pub fn structures(&self) -> &[Structure] {
+        &self.terrain.structures
+    }
+
+    /// Block at the position, with the structures and the player's edits applied on top of the
+    /// generated terrain. Rows out of the world are air.
+    pub fn get_block(&self, pos: BlockPos) -> Block {
+        if !pos.is_inside(&self.config) {
+            return Block::Air;
+        }
+        let block = match self.get_placed_block(pos) {
+            Some(block) => block,
+            None if !self.is_solid(pos) => Block::Air,
+            None if self.is_stone(pos) => match self.get_ore(pos) {
+                Some(item_type) => Block::Solid(SolidBlock::Ore(item_type)),
+                None => Block::Solid(SolidBlock::Stone),
+            },
+            None => Block::Solid(SolidBlock::Earth),
         };
 
+        match block {
+            Block::Solid(SolidBlock::Earth | SolidBlock::Surface(_)) => {
+                if self.is_solid(pos.up()) {
+                    Block::Solid(SolidBlock::Earth)
+                } else {
+                    Block::Solid(SolidBlock::Surface(self.get_biome(pos)))
+                }
+            }
+            block => block,
+        }
+    }
+
+    /// Block under a position in pixels.
+    pub fn block_at(&self, position: WorldPos) -> Block {
+        self.get_block(BlockPos::from_world(position, &self.config))
+    }
+
+    /// Top block of the generated ground in the column, caves aside.
+    pub fn surface_at(&self, pos: BlockPos) -> BlockPos {
+        BlockPos::new(pos.x, self.get_height_in_blocks(pos.x).ceil() as i32 - 1).wrap(&self.config)
+    }
+
+    /// Highest solid block of the column, stamped structures included, so things resting on it
+    /// end up on top of walls and roofs instead of inside them.
+    pub fn top_solid_at(&self, pos: BlockPos) -> BlockPos {
+        let surface = self.surface_at(pos);
+        (0..self.config.world_height as i32)
+            .rev()
+            .map(|y| BlockPos::new(surface.x, y))
+            .find(|pos| self.is_solid(*pos))
+            .unwrap_or(surface)
+    }
+
+    /// Block the character and the first enemies drop in from, above the spawn shrine at the origin.
+    pub fn spawn_point(&self) -> BlockPos {
+        let origin = BlockPos::from_world(WorldPos::ZERO, &self.config);
+        self.surface_at(origin).offset(0, SPAWN_HEIGHT)
+    }
+
+    fn get_height_in_blocks(&self, x: i32) -> f32 {
+        let height_at =
+            |x: i32| self.terrain.surface_height[x.rem_euclid(self.width) as usize].trunc();
+        let height = height_at(x);
+        let left_height = height_at(x - 1);
+        let right_height = height_at(x + 1);
+
         if height > left_height && height > right_height {
             left_height.max(right_height)
         } else {
@@ -40,19 +200,181 @@ impl GameWorld {
         }
     }
 
-    pub fn get_block_position(x: usize, y: usize) -> Vec2 {
-        Vec2::new(
-            ((x * BLOCK_SIZE) as f32).trunc(),
-            ((y * BLOCK_SIZE) as f32).trunc(),
-        )
+    pub fn get_biome(&self, pos: BlockPos) -> Biome {
+        Biome::dominant(&self.terrain.biome_weights[pos.wrap(&self.config).x as usize])
+    }
+
+    /// Whether the density field carves the block out. Caves get harder to open close to the
+    /// surface, so only the strongest ones break through it.
+    pub fn is_cave(&self, pos: BlockPos) -> bool {
+        if pos.y < BEDROCK_HEIGHT {
+            return false;
+        }
+        let pos = pos.wrap(&self.config);
+        let depth = self.get_height_in_blocks(pos.x) - pos.y as f32;
+        let falloff = (CAVE_CRUST_DEPTH - depth).max(0.) as f64 * CAVE_SURFACE_FALLOFF;
+        self.terrain
+            .cave_map
+            .get_value(pos.x as usize, pos.y as usize)
+            > CAVE_THRESHOLD + falloff
+    }
+
+    /// Whether the block is solid, structures and edits included. Rows below the world are
+    /// solid, rows above it are air.
+    pub fn is_solid(&self, pos: BlockPos) -> bool {
+        if pos.y < 0 {
+            return true;
+        }
+        match self.get_placed_block(pos) {
+            Some(block) => block != Block::Air,
+            None => (pos.y as f32) < self.get_height_in_blocks(pos.x) && !self.is_cave(pos),
+        }
     }
 
-    pub fn get_random_x_block(&self) -> usize {
-        let mut rng = thread_rng();
-        rng.gen_range(0..self.width) as usize
+    /// First row of earth: everything below it is stone.
+    fn get_stone_height_in_blocks(&self, x: i32) -> f32 {
+        let x = x.rem_euclid(self.width) as usize;
+        (self.get_height_in_blocks(x as i32) - self.terrain.earth_depth[x])
+            .max(0.)
+            .trunc()
     }
 
-    pub fn get_surface(&self, x: usize) -> f32 {
-        (self.get_height_in_blocks(x) * (BLOCK_SIZE as f32)) - ((BLOCK_SIZE / 2) as f32).trunc()
+    pub fn is_stone(&self, pos: BlockPos) -> bool {
+        (pos.y as f32) < self.get_stone_height_in_blocks(pos.x)
     }
+
+    /// Ore embedded in the stone at the block, rarer ores show up deeper.
+    pub fn get_ore(&self, pos: BlockPos) -> Option<PickableItemType> {
+        if !self.is_stone(pos) || !pos.is_inside(&self.config) {
+            return None;
+        }
+        let pos = pos.wrap(&self.config);
+        let density = self
+            .terrain
+            .ore_map
+            .get_value(pos.x as usize, pos.y as usize);
+        if density <= ORE_THRESHOLD {
+            return None;
+        }
+        let depth = 1. - pos.y as f32 / self.get_stone_height_in_blocks(pos.x);
+        let layer = (depth * ORE_LAYERS.len() as f32) as usize;
+        Some(ORE_LAYERS[layer.min(ORE_LAYERS.len() - 1)])
+    }
+
+    /// Atlas index of the decorative prop standing on the surface block at the position, if any.
+    /// Props clump along the density noise and each biome has its own mix of them.
+    pub fn get_prop(&self, pos: BlockPos) -> Option<usize> {
+        let pos = pos.wrap(&self.config);
+        let biome = self.get_biome(pos);
+        //WyRand is a single word of state, cheap enough to seed for every surface block
+        let mut rng = WyRand::seed_from_u64(
+            self.terrain.prop_seed ^ ((pos.x as u64) << 32 | pos.y as u32 as u64),
+        );
+        if rng.gen::<f32>() >= biome.prop_density() * self.terrain.prop_density[pos.x as usize] {
+            return None;
+        }
+        Some(rand_weighted_item(biome.props(), &mut rng))
+    }
+
+    /// Random column of the world, on the ground row.
+    pub fn get_random_column<R: Rng + ?Sized>(&self, rng: &mut R) -> BlockPos {
+        BlockPos::new(rng.gen_range(0..self.width), 0)
+    }
+}
+
+fn generate_noise_map(seed: &WorldSeed, config: &WorldConfig) -> NoiseMap {
+    let fbm = Fbm::<Worley>::new(seed.noise_seed());
+    let bounds = config.world_width() as f64 * 0.0025;
+    PlaneMapBuilder::new(fbm) //new_fn(|point| perlin_2d(point.into(), &hasher))
+        .set_size(config.world_width(), 1)
+        .set_x_bounds(-bounds * 1., bounds * 1.)
+        .set_y_bounds(-bounds, bounds)
+        .build()
+}
+
+//The biome noise runs along the world's circumference and wraps around with it
+fn generate_biome_weights_vec(seed: &WorldSeed, config: &WorldConfig) -> Vec<[f32; 4]> {
+    let fbm = Fbm::<Perlin>::new(seed.noise_seed().wrapping_add(4)).set_octaves(2);
+    let bounds = config.world_width() as f64 * 0.004;
+    let noise_map = PlaneMapBuilder::new(fbm)
+        .set_size(config.world_width(), 1)
+        .set_x_bounds(-bounds, bounds)
+        .set_is_seamless(true)
+        .build();
+    (0..config.world_width())
+        .map(|x| Biome::weights(noise_map.get_value(x, 0) as f32 * 1.5))
+        .collect()
+}
+
+fn generate_surface_height_vec(
+    noise_map: &NoiseMap,
+    biome_weights: &[[f32; 4]],
+    config: &WorldConfig,
+) -> Vec<f32> {
+    let mut v = Vec::<f32>::with_capacity(config.world_width());
+    for (x, weights) in biome_weights.iter().enumerate() {
+        let amplitude: f32 = Biome::ALL
+            .iter()
+            .zip(weights)
+            .map(|(biome, weight)| biome.height_amplitude() * weight)
+            .sum();
+        v.push(
+            config.floor_median()
+                + noise_map.get_value(x, 0) as f32 * config.floor_threshold() * amplitude,
+        );
+    }
+    v
+}
+
+//Second noise pass over the whole world: the density field that carves caves, overhangs and tunnels
+fn generate_cave_map(seed: &WorldSeed, config: &WorldConfig) -> NoiseMap {
+    let fbm = Fbm::<Perlin>::new(seed.noise_seed().wrapping_add(1));
+    let x_bounds = config.world_width() as f64 * 0.03;
+    let y_bounds = config.world_height as f64 * 0.03;
+    PlaneMapBuilder::new(fbm)
+        .set_size(config.world_width(), config.world_height)
+        .set_x_bounds(-x_bounds, x_bounds)
+        .set_y_bounds(-y_bounds, y_bounds)
+        .set_is_seamless(true)
+        .build()
+}
+
+fn generate_earth_depth_vec(seed: &WorldSeed, config: &WorldConfig) -> Vec<f32> {
+    let fbm = Fbm::<Perlin>::new(seed.noise_seed().wrapping_add(2));
+    let bounds = config.world_width() as f64 * 0.01;
+    let noise_map = PlaneMapBuilder::new(fbm)
+        .set_size(config.world_width(), 1)
+        .set_x_bounds(-bounds, bounds)
+        .set_is_seamless(true)
+        .build();
+    (0..config.world_width())
+        .map(|x| EARTH_DEPTH + noise_map.get_value(x, 0) as f32 * EARTH_DEPTH_VARIATION)
+        .collect()
+}
+
+//Small, dense blobs so ores show up as veins instead of big pockets
+fn generate_ore_map(seed: &WorldSeed, config: &WorldConfig) -> NoiseMap {
+    let fbm = Fbm::<Perlin>::new(seed.noise_seed().wrapping_add(3)).set_octaves(3);
+    let x_bounds = config.world_width() as f64 * 0.12;
+    let y_bounds = config.world_height as f64 * 0.12;
+    PlaneMapBuilder::new(fbm)
+        .set_size(config.world_width(), config.world_height)
+        .set_x_bounds(-x_bounds, x_bounds)
+        .set_y_bounds(-y_bounds, y_bounds)
+        .set_is_seamless(true)
+        .build()
+}
+
+//Between 0 and 2, so it scales each biome's prop density around its average
+fn generate_prop_density_vec(seed: &WorldSeed, config: &WorldConfig) -> Vec<f32> {
+    let fbm = Fbm::<Perlin>::new(seed.noise_seed().wrapping_add(5)).set_octaves(2);
+    let bounds = config.world_width() as f64 * 0.02;
+    let noise_map = PlaneMapBuilder::new(fbm)
+        .set_size(config.world_width(), 1)
+        .set_x_bounds(-bounds, bounds)
+        .set_is_seamless(true)
+        .build();
+    (0..config.world_width())
+        .map(|x| (1. + noise_map.get_value(x, 0) as f32).clamp(0., 2.))
+        .collect()
 }
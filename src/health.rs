@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+
+use crate::game::AppState;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.register_type::<HealthPoints>()
+            .register_type::<Resistances>()
+            .register_type::<HitInvulnerability>()
+            .observe(on_damage)
+            .observe(on_heal)
+            .add_systems(
+                Update,
+                tick_invulnerable.run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Health of anything that can be hurt, in half hearts.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+pub struct HealthPoints {
+    pub max_full_hearts: u8,
+    pub current: u8,
+}
+
+impl HealthPoints {
+    pub fn full(hearts: u8) -> Self {
+        HealthPoints {
+            max_full_hearts: hearts,
+            current: hearts * 2,
+        }
+    }
+
+    pub fn max(&self) -> u8 {
+        self.max_full_hearts * 2
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum DamageKind {
+    Contact,
+    Stomp,
+    Attack,
+}
+
+/// Half hearts taken off every hit of each kind.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+pub struct Resistances {
+    pub contact: u8,
+    pub stomp: u8,
+    pub attack: u8,
+}
+
+impl Resistances {
+    fn reduce(&self, amount: u8, kind: DamageKind) -> u8 {
+        let resistance = match kind {
+            DamageKind::Contact => self.contact,
+            DamageKind::Stomp => self.stomp,
+            DamageKind::Attack => self.attack,
+        };
+        amount.saturating_sub(resistance)
+    }
+}
+
+/// Seconds of `Invulnerable` the entity gets after every hit that lands.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+pub struct HitInvulnerability(pub f32);
+
+/// Window after a hit where the entity takes no damage. Removed once the timer is done.
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+impl Invulnerable {
+    pub fn new(seconds: f32) -> Self {
+        Invulnerable(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+/// Triggered on an entity to hurt it. Resistances and invulnerability are applied before it
+/// lands, then `Damaged` and, when the health runs out, `Died` are triggered on the entity.
+/// Entities with `HitInvulnerability` that survive become `Invulnerable` for a while.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Damage {
+    pub amount: u8,
+    pub kind: DamageKind,
+}
+
+/// Triggered on an entity to give back health, up to its maximum. `Healed` follows.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Heal {
+    pub amount: u8,
+}
+
+/// Damage that landed, after resistances.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Damaged {
+    pub amount: u8,
+    pub kind: DamageKind,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Healed {
+    pub amount: u8,
+}
+
+/// The entity's health reached zero. Whoever owns the entity decides what dying means.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Died;
+
+fn on_damage(
+    trigger: Trigger<Damage>,
+    mut query: Query<(
+        &mut HealthPoints,
+        Option<&Resistances>,
+        Option<&HitInvulnerability>,
+        Has<Invulnerable>,
+    )>,
+    mut commands: Commands,
+) {
+    let entity = trigger.entity();
+    let Ok((mut health, resistances, hit_invulnerability, invulnerable)) = query.get_mut(entity)
+    else {
+        return;
+    };
+    //The dead stay dead, a second hit in the same frame would trigger `Died` again
+    if invulnerable || health.is_dead() {
+        return;
+    }
+    let damage = trigger.event();
+    let amount = resistances
+        .map_or(damage.amount, |resistances| {
+            resistances.reduce(damage.amount, damage.kind)
+        })
+        .min(health.current);
+    if amount == 0 {
+        return;
+    }
+    health.current -= amount;
+    commands.trigger_targets(
+        Damaged {
+            amount,
+            kind: damage.kind,
+        },
+        entity,
+    );
+    if health.is_dead() {
+        commands.trigger_targets(Died, entity);
+    } else if let Some(HitInvulnerability(seconds)) = hit_invulnerability {
+        commands.entity(entity).insert(Invulnerable::new(*seconds));
+    }
+}
+
+fn on_heal(trigger: Trigger<Heal>, mut query: Query<&mut HealthPoints>, mut commands: Commands) {
+    let entity = trigger.entity();
+    let Ok(mut health) = query.get_mut(entity) else {
+        return;
+    };
+    let amount = trigger
+        .event()
+        .amount
+        .min(health.max().saturating_sub(health.current));
+    if amount == 0 || health.is_dead() {
+        return;
+    }
+    health.current += amount;
+    commands.trigger_targets(Healed { amount }, entity);
+}
+
+fn tick_invulnerable(
+    mut query: Query<(Entity, &mut Invulnerable)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut invulnerable) in query.iter_mut() {
+        if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world_with(health: HealthPoints, resistances: Resistances) -> (World, Entity) {
+        let mut world = World::new();
+        world.observe(on_damage);
+        world.observe(on_heal);
+        let entity = world.spawn((health, resistances)).id();
+        (world, entity)
+    }
+
+    fn current(world: &World, entity: Entity) -> u8 {
+        world.get::<HealthPoints>(entity).unwrap().current
+    }
+
+    #[test]
+    fn resistances_reduce_the_damage_of_their_kind() {
+        let resistances = Resistances {
+            attack: 1,
+            ..default()
+        };
+        let (mut world, entity) = world_with(HealthPoints::full(3), resistances);
+        let hit = |kind| Damage { amount: 2, kind };
+        world.trigger_targets(hit(DamageKind::Attack), entity);
+        world.flush();
+        assert_eq!(current(&world, entity), 5);
+        world.trigger_targets(hit(DamageKind::Contact), entity);
+        world.flush();
+        assert_eq!(current(&world, entity), 3);
+    }
+
+    #[test]
+    fn invulnerable_entities_take_no_damage() {
+        let (mut world, entity) = world_with(HealthPoints::full(1), Resistances::default());
+        world.entity_mut(entity).insert(Invulnerable::new(1.));
+        world.trigger_targets(
+            Damage {
+                amount: 1,
+                kind: DamageKind::Contact,
+            },
+            entity,
+        );
+        world.flush();
+        assert_eq!(current(&world, entity), 2);
+    }
+
+    #[derive(Resource, Default)]
+    struct Deaths(u32);
+
+    #[test]
+    fn dying_triggers_died_once() {
+        let (mut world, entity) = world_with(HealthPoints::full(1), Resistances::default());
+        world.init_resource::<Deaths>();
+        world.observe(|_: Trigger<Died>, mut deaths: ResMut<Deaths>| deaths.0 += 1);
+        for _ in 0..3 {
+            world.trigger_targets(
+                Damage {
+                    amount: 5,
+                    kind: DamageKind::Stomp,
+                },
+                entity,
+            );
+            world.flush();
+        }
+        assert_eq!(current(&world, entity), 0);
+        assert_eq!(world.resource::<Deaths>().0, 1);
+    }
+
+    #[test]
+    fn healing_stops_at_the_maximum() {
+        let (mut world, entity) = world_with(HealthPoints::full(2), Resistances::default());
+        world.trigger_targets(
+            Damage {
+                amount: 3,
+                kind: DamageKind::Contact,
+            },
+            entity,
+        );
+        world.flush();
+        world.trigger_targets(Heal { amount: 10 }, entity);
+        world.flush();
+        assert_eq!(current(&world, entity), 4);
+    }
+}
@@ -0,0 +1,61 @@
+// Bevy code commonly triggers these lints and they may be important signals
+// about code quality. They are sometimes hard to avoid though, and the CI
+// workflow treats them as errors, so this allows them throughout the project.
+// Feel free to delete this line.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+//TODO: Ponderar sobre tamanho do bloco, tamanho do chunk, tamanho do mundo
+
+//TODO: Dar créditos
+/*
+bgp_catdev: https://catdev-pixelarts.itch.io/basic-platform-pixel-art-pack
+purple-valley-icon-set: https://opengameart.org/content/purple-valley-icon-set
+ */
+
+/**
+ *
+ * 20:26BigardiDEV: em resumo é um AABB com um for loop da posição antiga pra próxima posição pra evitar de passar por colisores quando tiver rapido
+ * 20:27BigardiDEV: pra otimizar mete um spatial hashing baseado em grid que tá show
+ * 20:28BigardiDEV: você tem lá seus grids que são "baldes" que seguram uma lista de entidades nele baseando na posição, aí cada entidade só precisa verificar a colisão com os baldes vizinhos, evita o big O notation
+ */
+pub mod autotile;
+pub mod background;
+pub mod biome;
+pub mod camera;
+pub mod character;
+pub mod combat;
+pub mod config;
+pub mod control;
+pub mod coordinates;
+pub mod digging;
+pub mod enemy;
+pub mod fixtures;
+pub mod game;
+pub mod game_over;
+pub mod game_world;
+pub mod health;
+pub mod loading;
+pub mod map;
+pub mod menu;
+pub mod pickables;
+pub mod seed;
+pub mod structures;
+pub mod theme;
+pub mod tilemap;
+pub mod ui;
+pub mod utils;
+pub mod wraparound;
+
+use bevy::render::view::RenderLayers;
+
+pub const BACKGROUND_LAYERS: RenderLayers = RenderLayers::layer(0);
+pub const PIXEL_PERFECT_LAYERS: RenderLayers = RenderLayers::layer(0);
+pub const HIGH_RES_LAYERS: RenderLayers = RenderLayers::layer(1);
+
+pub const CANVAS_HEIGHT: usize = 432; //Canvas height in pixels
+                                      //Canvas width based on height to keep 16:9 aspect ratio
+pub const CANVAS_WIDTH: usize = CANVAS_HEIGHT / 9 * 16;
+
+pub const CHARACTER_ROAMING_THRESHOLD: usize = CANVAS_WIDTH * 4 / 10;
+
+const GRAVITY: f32 = -9.81;
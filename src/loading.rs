@@ -0,0 +1,147 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{game::AppState, theme::ThemedImage, HIGH_RES_LAYERS};
+
+const BAR_WIDTH: f32 = 400.;
+const BAR_HEIGHT: f32 = 24.;
+const BAR_BACKGROUND_COLOR: Color = Color::srgb(0.2, 0.2, 0.25);
+const BAR_COLOR: Color = Color::srgb(0.45, 0.75, 0.45);
+const ERROR_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<RequiredAssets>()
+            .add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+            .add_systems(Update, check_assets.run_if(in_state(AppState::Loading)));
+    }
+}
+
+/// Handles that have to be loaded before the game leaves `AppState::Loading`. Every module
+/// that loads assets at `Startup` adds its handles here.
+#[derive(Resource, Default)]
+pub struct RequiredAssets(Vec<UntypedHandle>);
+
+impl RequiredAssets {
+    pub fn track(&mut self, handle: impl Into<UntypedHandle>) {
+        self.0.push(handle.into());
+    }
+
+    pub fn track_themed(&mut self, image: &ThemedImage) {
+        self.track(image.standard.clone());
+        self.track(image.white.clone());
+    }
+}
+
+#[derive(Component)]
+struct ProgressBarUI;
+
+#[derive(Component)]
+struct LoadingErrorTextUI;
+
+//The font is one of the assets being loaded, so the screen is written with Bevy's default one
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Loading UI"),
+            StateScoped(AppState::Loading),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                ..default()
+            },
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Loading",
+                TextStyle {
+                    font_size: 42.0,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(BAR_HEIGHT),
+                        ..default()
+                    },
+                    background_color: BAR_BACKGROUND_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn((
+                        ProgressBarUI,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR_COLOR.into(),
+                            ..default()
+                        },
+                    ));
+                });
+            parent.spawn((
+                LoadingErrorTextUI,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: ERROR_COLOR,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+//Fills the bar with the loaded share of the assets, lists the ones that failed and stays here
+//while there are any
+fn check_assets(
+    required_assets: Res<RequiredAssets>,
+    asset_server: Res<AssetServer>,
+    mut bar_query: Query<&mut Style, With<ProgressBarUI>>,
+    mut error_text_query: Query<&mut Text, With<LoadingErrorTextUI>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut loaded = 0;
+    let mut errors = Vec::new();
+    for handle in &required_assets.0 {
+        match asset_server.get_load_state(handle.id()) {
+            Some(LoadState::Loaded) => loaded += 1,
+            Some(LoadState::Failed(error)) => errors.push(format!(
+                "Failed to load {}: {}",
+                handle
+                    .path()
+                    .map_or("an asset".to_string(), |path| path.to_string()),
+                error
+            )),
+            _ => {}
+        }
+    }
+
+    let total = required_assets.0.len().max(1);
+    for mut style in bar_query.iter_mut() {
+        style.width = Val::Percent(100. * loaded as f32 / total as f32);
+    }
+    if !errors.is_empty() {
+        for mut text in error_text_query.iter_mut() {
+            text.sections[0].value = errors.join("\n");
+        }
+        return;
+    }
+    if loaded == required_assets.0.len() {
+        next_state.set(AppState::Menu);
+    }
+}
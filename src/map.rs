@@ -1,393 +1,504 @@
 use bevy::{
     app::{Plugin, Update},
     asset::{AssetServer, Assets, Handle},
-    color::Color,
     hierarchy::{BuildChildren, DespawnRecursiveExt},
     math::{UVec2, Vec2, Vec3},
     prelude::*,
-    sprite::{Sprite, SpriteBundle, TextureAtlas, TextureAtlasLayout},
+    sprite::{ColorMaterial, MaterialMesh2dBundle, TextureAtlasLayout},
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
     transform::components::Transform,
+    utils::{HashMap, HashSet},
 };
 use bevy_rapier2d::prelude::Collider;
 
 use crate::{
-    camera::InGameCamera, game_world::GameWorld, BLOCK_SIZE, CHUNKS_TO_LOAD, CHUNK_COUNT,
-    CHUNK_INITIAL_OFFSET, CHUNK_WIDTH, PIXEL_PERFECT_LAYERS, WORLD_BOTTOM_OFFSET, WORLD_CENTER_COL,
-    WORLD_HEIGHT, WORLD_WIDTH,
+    autotile::AutotileRules,
+    biome::Biome,
+    coordinates::{BlockPos, ChunkPos, WorldPos},
+    game::{AppState, DespawnWorld, GameStartupSet, SpawnWorld},
+    game_world::GameWorld,
+    loading::RequiredAssets,
+    pickables::PickableItemType,
+    theme::{Theme, ThemedImage},
+    tilemap::{build_chunk_meshes, ChunkMeshes},
+    wraparound::WorldWrap,
+    PIXEL_PERFECT_LAYERS,
 };
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, (load_textures, startup).chain())
-            .add_systems(Update, map_movement);
+        app.insert_resource(AutotileRules::load())
+            .observe(on_chunk_edited)
+            .add_systems(Startup, load_textures)
+            //Pickables have to be there before the first chunks ask for theirs
+            .add_systems(SpawnWorld, startup.after(GameStartupSet::Pickables))
+            .add_systems(DespawnWorld, despawn_chunks.in_set(DespawnChunksSet))
+            .add_systems(
+                Update,
+                (stream_chunks, commit_chunks)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(Update, apply_theme.run_if(resource_changed::<Theme>));
     }
 }
 
+//One material per texture the chunk meshes are drawn with
 #[derive(Resource)]
 struct Tiles {
-    standard: Handle<Image>,
-    white: Handle<Image>,
+    image: ThemedImage,
+    standard: Handle<ColorMaterial>, //material of the standard tileset, its texture follows the theme
+    white: Handle<ColorMaterial>,
+    colors: Handle<ColorMaterial>,
 }
 
+/// Where `DespawnWorld` unloads the chunks. Teardown systems that despawn what the
+/// `ChunkUnloadingEvent` listeners already handle go after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DespawnChunksSet;
+
 #[derive(Resource)]
 struct TilesAtlasLayout(Handle<TextureAtlasLayout>);
 
+/// Keeps the chunks within `radius_in_chunks` of the entity loaded, on both sides. Chunks span
+/// the whole height of the world, so going up or down never needs other chunks.
+#[derive(Component)]
+pub struct ChunkLoader {
+    pub radius_in_chunks: usize,
+}
+
 #[derive(Component)]
 pub struct Chunk {
-    pub index: usize,
+    pub index: ChunkPos,
+    pub state: ChunkState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkState {
+    //Spawned, but its blocks are still being built
+    Loading,
+    ChunkLoaded,
+    //About to be despawned, only seen by `ChunkUnloadingEvent` observers
+    ChunkUnloaded,
+}
+
+//Everything about a chunk that is expensive to compute, so it can be built off the main thread
+struct ChunkData {
+    meshes: ChunkMeshes,
+    collider: Collider,
 }
 
+//Chunk still being built in the background, it has no blocks until the task is done
+#[derive(Component)]
+struct ChunkTask(Task<ChunkData>);
+
 #[derive(Event)]
 pub struct NewChunkEvent {
     pub chunk: Entity,
 }
 
-#[derive(PartialEq)]
-enum Block {
+/// Triggered right before a chunk is despawned, while the chunk and its children still exist,
+/// so whatever lives in it can be stored and restored on a later `NewChunkEvent`.
+/// Also triggered for every chunk when `DespawnWorld` tears the world down.
+#[derive(Event)]
+pub struct ChunkUnloadingEvent {
+    pub chunk: Entity,
+}
+
+/// Triggered after a block of the chunk is dug or placed, so the loaded chunk is rebuilt.
+#[derive(Event)]
+pub struct ChunkEditedEvent {
+    pub chunk_index: ChunkPos,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Block {
     Air,
     Solid(SolidBlock),
 }
 
-#[derive(PartialEq)]
-enum SolidBlock {
-    Surface,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SolidBlock {
+    Surface(Biome),
     Stone,
     Earth,
+    Ore(PickableItemType),
+}
+
+pub fn tiles_atlas_layout() -> TextureAtlasLayout {
+    TextureAtlasLayout::from_grid(UVec2::new(16, 16), 7, 5, None, None)
 }
 
 fn load_textures(
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
     asset_server: Res<AssetServer>,
+    mut required_assets: ResMut<RequiredAssets>,
     mut commands: Commands,
 ) {
-    let atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(16, 16), 7, 5, None, None);
-    commands.insert_resource(TilesAtlasLayout(texture_atlases.add(atlas_layout)));
+    commands.insert_resource(TilesAtlasLayout(texture_atlases.add(tiles_atlas_layout())));
+    let image = ThemedImage::load(&asset_server, "bgp_catdev/Tillesets/Basic_GrassAndProps");
+    required_assets.track_themed(&image);
     commands.insert_resource(Tiles {
-        standard: asset_server.load("bgp_catdev/Tillesets/Basic_GrassAndProps.png"),
-        white: asset_server.load("bgp_catdev/Tillesets/Basic_GrassAndPropsWhiteVer.png"),
+        standard: materials.add(ColorMaterial::from(image.standard.clone())),
+        white: materials.add(ColorMaterial::from(image.white.clone())),
+        image,
+        colors: materials.add(ColorMaterial::default()),
     });
 }
 
+//Chunk meshes keep their material handles, so swapping the texture rethemes every chunk at once
+fn apply_theme(theme: Res<Theme>, tiles: Res<Tiles>, mut materials: ResMut<Assets<ColorMaterial>>) {
+    if let Some(material) = materials.get_mut(&tiles.standard) {
+        material.texture = Some(tiles.image.get(*theme));
+    }
+}
+
 fn startup(
     mut commands: Commands,
     game_world: Res<GameWorld>,
     atlas_layout: Res<TilesAtlasLayout>,
+    texture_atlases: Res<Assets<TextureAtlasLayout>>,
+    rules: Res<AutotileRules>,
+    mut meshes: ResMut<Assets<Mesh>>,
     tiles: Res<Tiles>,
 ) {
-    let half_chunks_to_load = CHUNKS_TO_LOAD as i32 / 2;
-    let remaining_chunks_to_load = CHUNKS_TO_LOAD as i32 % 2;
-
-    for i in -half_chunks_to_load..(half_chunks_to_load + remaining_chunks_to_load) {
-        let chunk_index = ((CHUNK_INITIAL_OFFSET as i32) + i) as usize;
-        let x: f32 = (i * (CHUNK_WIDTH * BLOCK_SIZE) as i32) as f32;
+    let atlas_layout = texture_atlases.get(&atlas_layout.0).unwrap();
+    let config = &game_world.config;
+    let half_chunks_to_load = config.chunks_to_load as i32 / 2;
+    let remaining_chunks_to_load = config.chunks_to_load as i32 % 2;
 
+    for slot in -half_chunks_to_load..(half_chunks_to_load + remaining_chunks_to_load) {
         new_chunk(
-            chunk_index,
+            ChunkPos::from_slot(slot, config),
             &game_world,
-            x,
+            WorldPos::from_slot(slot, config),
             &mut commands,
             &tiles,
-            atlas_layout.0.clone(),
+            atlas_layout,
+            &rules,
+            &mut meshes,
         );
     }
 }
 
-fn new_earth_block(
-    x: usize,
-    y: usize,
-    atlas_layout_handle: Handle<TextureAtlasLayout>,
-    tiles: &Tiles,
-    around_blocks: [Block; 9],
-) -> (SpriteBundle, TextureAtlas) {
-    let index = match around_blocks {
-        [_, _, _, Block::Air, _, _, _, _, _] => 7,  // left
-        [_, _, _, _, _, Block::Air, _, _, _] => 10, // right
-        [Block::Air, _, _, _, _, _, _, _, _] => 12, // up left corner
-        [_, _, Block::Air, _, _, _, _, _, _] => 11, // up right corner
-        [_, _, _, _, _, _, _, _, Block::Air] => 4,  // down right corner
-        [_, _, _, _, _, _, Block::Air, _, _] => 5,  // down left corner
-        _ => 8,                                     // center
-    };
-
-    new_block_from_tilesheet(
-        x,
-        y,
-        tiles.standard.clone(),
-        atlas_layout_handle.clone(),
-        index,
-    )
-}
-
-fn new_stone_block(x: usize, y: usize) -> SpriteBundle {
-    new_block_color(x, y, Color::linear_rgb(0.5, 0.5, 0.5))
+//Unloaded like any other chunk, so the listeners let go of what lives in them. Chunks still
+//being built go too, dropping their task cancels it
+fn despawn_chunks(mut query: Query<(Entity, &mut Chunk)>, mut commands: Commands) {
+    for (entity, mut chunk) in query.iter_mut() {
+        unload_chunk(entity, &mut chunk, &mut commands);
+    }
 }
 
-fn new_surface_block(
-    x: usize,
-    y: usize,
-    atlas_layout_handle: Handle<TextureAtlasLayout>,
-    tiles: &Tiles,
-    around_blocks: [Block; 9],
-) -> (SpriteBundle, TextureAtlas) {
-    let index = match around_blocks {
-        [_, Block::Air, _, Block::Air, _, _, _, _, _] => 0, // up left
-        [_, Block::Air, _, _, _, Block::Air, _, _, _] => 3, // up right
-        [_, _, _, _, _, Block::Air, _, Block::Air, _] => 24, // down right
-        [_, _, _, Block::Air, _, _, Block::Air, _, _] => 21, // down left
-        _ => 1,                                             // up
-    };
-
-    new_block_from_tilesheet(
-        x,
-        y,
-        tiles.standard.clone(),
-        atlas_layout_handle.clone(),
-        index,
-    )
+fn build_chunk_data(
+    chunk_index: ChunkPos,
+    game_world: &GameWorld,
+    atlas_layout: &TextureAtlasLayout,
+    rules: &AutotileRules,
+) -> ChunkData {
+    ChunkData {
+        meshes: build_chunk_meshes(chunk_index, game_world, atlas_layout, rules),
+        collider: new_chunk_collider(game_world, chunk_index),
+    }
 }
 
-fn new_block_from_tilesheet(
-    x: usize,
-    y: usize,
-    tiles: Handle<Image>,
-    atlas_handle: Handle<TextureAtlasLayout>,
-    index: usize,
-) -> (SpriteBundle, TextureAtlas) {
-    (
-        SpriteBundle {
-            texture: tiles,
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(BLOCK_SIZE as f32, BLOCK_SIZE as f32)),
+fn spawn_chunk(chunk_index: ChunkPos, position: WorldPos, commands: &mut Commands) -> Entity {
+    commands
+        .spawn((
+            SpatialBundle {
+                transform: Transform::from_translation(position.0.extend(2.)),
                 ..default()
             },
-            transform: Transform::from_translation(GameWorld::get_block_position(x, y).extend(2.)),
-            ..default()
-        },
-        TextureAtlas {
-            layout: atlas_handle,
-            index: index,
-            ..Default::default()
-        },
-    )
-}
-
-fn new_block_color(x: usize, y: usize, color: Color) -> SpriteBundle {
-    SpriteBundle {
-        sprite: Sprite {
-            color: color,
-            custom_size: Some(Vec2::new(BLOCK_SIZE as f32, BLOCK_SIZE as f32)),
-            ..default()
-        },
-        transform: Transform::from_translation(Vec3::new(
-            (x * BLOCK_SIZE) as f32,
-            (y * BLOCK_SIZE) as f32,
-            2.,
-        )),
-        ..default()
-    }
+            Chunk {
+                index: chunk_index,
+                state: ChunkState::Loading,
+            },
+            WorldWrap,
+        ))
+        .id()
 }
 
+//Builds the chunk right away, for chunks that have to be there in the same frame
 fn new_chunk(
-    chunk_index: usize,
+    chunk_index: ChunkPos,
     game_world: &GameWorld,
-    x: f32,
+    position: WorldPos,
     commands: &mut Commands,
     tiles: &Tiles,
-    atlas_layout_handle: Handle<TextureAtlasLayout>,
+    atlas_layout: &TextureAtlasLayout,
+    rules: &AutotileRules,
+    meshes: &mut Assets<Mesh>,
 ) {
-    let start_x = CHUNK_WIDTH * chunk_index;
-    let y = (WORLD_BOTTOM_OFFSET * BLOCK_SIZE as i32) as f32;
-    let start_y: usize = 0;
+    let chunk_entity = spawn_chunk(chunk_index, position, commands);
+    let chunk_data = build_chunk_data(chunk_index, game_world, atlas_layout, rules);
+    fill_chunk(
+        chunk_entity,
+        chunk_index,
+        chunk_data,
+        commands,
+        tiles,
+        meshes,
+    );
+}
 
-    let chunk = Chunk { index: chunk_index };
-    let chunk_entity = commands
-        .spawn((
-            SpatialBundle {
-                transform: Transform::from_xyz(x, y, 2.),
-                ..default()
-            },
-            chunk,
-        ))
-        .with_children(|parent| {
-            if chunk_index == (WORLD_WIDTH / CHUNK_WIDTH / 2) {
-                parent.spawn((
-                    new_stone_block(
-                        0,
-                        game_world.get_height_in_blocks(WORLD_CENTER_COL) as usize + 10,
-                    ),
-                    PIXEL_PERFECT_LAYERS,
-                ));
-            }
-            for col_x in 0..CHUNK_WIDTH {
-                for col_y in 0..WORLD_HEIGHT {
-                    let x = start_x + col_x;
-                    let y = start_y + col_y;
-
-                    match get_block(x, y, &game_world) {
-                        Block::Air => {}
-                        Block::Solid(SolidBlock::Earth) => {
-                            let around_blocks = get_around_blocks(x, y, game_world);
-
-                            parent.spawn((
-                                new_earth_block(
-                                    col_x,
-                                    col_y,
-                                    atlas_layout_handle.clone(),
-                                    &tiles,
-                                    around_blocks,
-                                ),
-                                PIXEL_PERFECT_LAYERS,
-                            ));
-                        }
-                        Block::Solid(SolidBlock::Stone) => {
-                            parent.spawn((new_stone_block(col_x, col_y), PIXEL_PERFECT_LAYERS));
-                        }
-                        Block::Solid(SolidBlock::Surface) => {
-                            let around_blocks = get_around_blocks(x, y, game_world);
-
-                            parent.spawn((
-                                new_surface_block(
-                                    col_x,
-                                    col_y,
-                                    atlas_layout_handle.clone(),
-                                    &tiles,
-                                    around_blocks,
-                                ),
-                                PIXEL_PERFECT_LAYERS,
-                            ));
-                        }
-                    }
-                }
-            }
+//Spawns an empty chunk and builds its blocks on the AsyncComputeTaskPool
+fn queue_chunk(
+    chunk_index: ChunkPos,
+    game_world: &GameWorld,
+    position: WorldPos,
+    commands: &mut Commands,
+    atlas_layout: &TextureAtlasLayout,
+    rules: &AutotileRules,
+) {
+    let chunk_entity = spawn_chunk(chunk_index, position, commands);
+    let game_world = game_world.clone();
+    let atlas_layout = atlas_layout.clone();
+    let rules = rules.clone();
+    let task = AsyncComputeTaskPool::get()
+        .spawn(async move { build_chunk_data(chunk_index, &game_world, &atlas_layout, &rules) });
+    commands.entity(chunk_entity).insert(ChunkTask(task));
+}
+
+fn fill_chunk(
+    chunk_entity: Entity,
+    chunk_index: ChunkPos,
+    chunk_data: ChunkData,
+    commands: &mut Commands,
+    tiles: &Tiles,
+    meshes: &mut Assets<Mesh>,
+) {
+    commands.entity(chunk_entity).with_children(|parent| {
+        //Props sit between the background and the terrain, without colliders
+        for (mesh, material, z) in [
+            (chunk_data.meshes.standard, &tiles.standard, 2.),
+            (chunk_data.meshes.white, &tiles.white, 2.),
+            (chunk_data.meshes.colors, &tiles.colors, 2.),
+            (chunk_data.meshes.standard_props, &tiles.standard, 1.),
+            (chunk_data.meshes.white_props, &tiles.white, 1.),
+        ] {
+            let Some(mesh) = mesh else {
+                continue;
+            };
             parent.spawn((
-                TransformBundle {
-                    local: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+                MaterialMesh2dBundle {
+                    mesh: meshes.add(mesh).into(),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(0., 0., z),
                     ..default()
                 },
-                Collider::polyline(new_chunk_polyline(game_world, chunk_index), Option::None),
+                PIXEL_PERFECT_LAYERS,
             ));
-        })
-        .id();
+        }
+        parent.spawn((
+            TransformBundle {
+                local: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+                ..default()
+            },
+            chunk_data.collider,
+        ));
+    });
+    commands.entity(chunk_entity).insert(Chunk {
+        index: chunk_index,
+        state: ChunkState::ChunkLoaded,
+    });
     commands.trigger(NewChunkEvent {
         chunk: chunk_entity,
     });
 }
 
-fn new_chunk_polyline(game_world: &GameWorld, chunk_index: usize) -> Vec<Vec2> {
-    let mut vertices = Vec::<Vec2>::with_capacity(CHUNK_WIDTH * 2 + 4);
-
-    let range_start = (chunk_index * CHUNK_WIDTH) as i32;
-    vertices.push(Vec2::new(-(BLOCK_SIZE as i32 / 2) as f32, 0.0));
-    vertices.push(Vec2::new(
-        -(BLOCK_SIZE as i32 / 2) as f32,
-        game_world.get_surface(range_start as usize),
-    ));
-
-    for x in range_start..(range_start + CHUNK_WIDTH as i32) {
-        let y = game_world.get_surface(x as usize);
-        let y2 = game_world.get_surface((x + 1) as usize);
-        vertices.push(Vec2::new(
-            ((x - range_start + 1) * (BLOCK_SIZE) as i32 - (BLOCK_SIZE / 2) as i32) as f32,
-            y,
-        ));
-        if y * BLOCK_SIZE as f32 != y2 {
-            vertices.push(Vec2::new(
-                ((x - range_start + 1) * (BLOCK_SIZE) as i32 - (BLOCK_SIZE / 2) as i32) as f32,
-                y2,
-            ));
+fn unload_chunk(chunk_entity: Entity, chunk: &mut Chunk, commands: &mut Commands) {
+    chunk.state = ChunkState::ChunkUnloaded;
+    commands.trigger(ChunkUnloadingEvent {
+        chunk: chunk_entity,
+    });
+    commands.entity(chunk_entity).despawn_recursive();
+}
+
+fn commit_chunks(
+    mut query: Query<(Entity, &Chunk, &mut ChunkTask)>,
+    mut commands: Commands,
+    tiles: Res<Tiles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, chunk, mut task) in query.iter_mut() {
+        let Some(chunk_data) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        commands.entity(entity).remove::<ChunkTask>();
+        fill_chunk(
+            entity,
+            chunk.index,
+            chunk_data,
+            &mut commands,
+            &tiles,
+            &mut meshes,
+        );
+    }
+}
+
+//Outline of the solid blocks: every block side that faces air, with the sides along the same line
+//merged into one segment. A chunk owns the wall on its left border but not on its right one, and
+//floors crossing a border reach half a block into the neighbour chunk, so neighbouring chunks
+//overlap instead of meeting at a corner the character could catch on.
+fn new_chunk_collider(game_world: &GameWorld, chunk_index: ChunkPos) -> Collider {
+    let config = &game_world.config;
+    let block_size = config.block_size as f32;
+    let half_block = block_size / 2.;
+    let chunk_width = config.chunk_width as i32;
+    let world_height = config.world_height as i32;
+    let first_block = chunk_index.first_block(config);
+
+    //Columns out of the chunk wrap around the world, rows below it are never open
+    let is_solid = |col_x: i32, y: i32| game_world.is_solid(first_block.offset(col_x, y));
+    //Lines run between blocks: line `n` is the left (or bottom) side of block `n`
+    let line = |n: i32| n as f32 * block_size - half_block;
+    let has_floor = |col_x: i32, row: i32| is_solid(col_x, row - 1) != is_solid(col_x, row);
+    let has_wall = |col: i32, y: i32| is_solid(col - 1, y) != is_solid(col, y);
+
+    let mut segments = Vec::<[Vec2; 2]>::new();
+    for row in 0..=world_height {
+        let mut col_x = 0;
+        while col_x < chunk_width {
+            if !has_floor(col_x, row) {
+                col_x += 1;
+                continue;
+            }
+            let run_start = col_x;
+            while col_x < chunk_width && has_floor(col_x, row) {
+                col_x += 1;
+            }
+            let mut start = line(run_start);
+            let mut end = line(col_x);
+            if run_start == 0 && has_floor(-1, row) {
+                start -= half_block;
+            }
+            if col_x == chunk_width && has_floor(chunk_width, row) {
+                end += half_block;
+            }
+            segments.push([Vec2::new(start, line(row)), Vec2::new(end, line(row))]);
         }
     }
-    vertices.push(Vec2::new(
-        (CHUNK_WIDTH as i32 * BLOCK_SIZE as i32 - (BLOCK_SIZE / 2) as i32) as f32,
-        0.0,
-    ));
-    vertices.push(Vec2::new(-(BLOCK_SIZE as i32 / 2) as f32, 0.0));
-    vertices
+    for col in 0..chunk_width {
+        let mut y = 0;
+        while y < world_height {
+            if !has_wall(col, y) {
+                y += 1;
+                continue;
+            }
+            let run_start = y;
+            while y < world_height && has_wall(col, y) {
+                y += 1;
+            }
+            segments.push([
+                Vec2::new(line(col), line(run_start)),
+                Vec2::new(line(col), line(y)),
+            ]);
+        }
+    }
+
+    let vertices = segments.iter().flatten().copied().collect();
+    let indices = (0..segments.len() as u32)
+        .map(|i| [i * 2, i * 2 + 1])
+        .collect();
+    Collider::polyline(vertices, Some(indices))
 }
 
-fn map_movement(
-    cam_query: Query<&InGameCamera>,
-    query: Query<(Entity, &Transform, &Chunk)>,
+fn stream_chunks(
+    loaders: Query<(&Transform, &ChunkLoader)>,
+    mut query: Query<(Entity, &Transform, &mut Chunk)>,
     mut commands: Commands,
     game_world: Res<GameWorld>,
-    tiles: Res<Tiles>,
     atlas_layout_handle: Res<TilesAtlasLayout>,
+    texture_atlases: Res<Assets<TextureAtlasLayout>>,
+    rules: Res<AutotileRules>,
 ) {
-    let camera = cam_query.single();
-    for (entity, transform, chunk) in query.iter() {
-        let camera_offset = camera.translation.x - transform.translation.x;
-        if (camera.is_going_right
-            && camera_offset > camera.chunk_unload_after
-            && transform.translation.x < camera.translation.x)
-            || (!camera.is_going_right
-                && camera_offset < -camera.chunk_unload_after
-                && transform.translation.x > camera.translation.x)
-        {
-            let (new_chunk_offset, next_index) = if camera.is_going_right {
-                (
-                    transform.translation.x + (CHUNKS_TO_LOAD * CHUNK_WIDTH * BLOCK_SIZE) as f32,
-                    chunk.index as i32 + CHUNKS_TO_LOAD as i32,
-                )
-            } else {
-                (
-                    transform.translation.x - (CHUNKS_TO_LOAD * CHUNK_WIDTH * BLOCK_SIZE) as f32,
-                    chunk.index as i32 - CHUNKS_TO_LOAD as i32,
-                )
-            };
-            let chunk_index = ((next_index + CHUNK_COUNT as i32) % CHUNK_COUNT as i32) as usize;
-            new_chunk(
-                chunk_index,
-                &game_world,
-                new_chunk_offset,
-                &mut commands,
-                &tiles,
-                atlas_layout_handle.0.clone(),
-            );
-
-            commands.entity(entity).despawn_recursive();
+    let atlas_layout = texture_atlases.get(&atlas_layout_handle.0).unwrap();
+    let config = &game_world.config;
+
+    //Chunks load within the radius but only unload one chunk past it, so walking back and
+    //forth over a chunk border doesn't rebuild the same chunk over and over
+    let mut to_load = HashSet::<i32>::default();
+    let mut to_keep = HashSet::<i32>::default();
+    for (transform, loader) in loaders.iter() {
+        let slot = WorldPos(transform.translation.truncate()).chunk_slot(config);
+        let radius = loader.radius_in_chunks as i32;
+        to_load.extend((slot - radius)..=(slot + radius));
+        to_keep.extend((slot - radius - 1)..=(slot + radius + 1));
+    }
+
+    let mut loaded = HashMap::<i32, Entity>::default();
+    for (entity, transform, mut chunk) in query.iter_mut() {
+        let slot = WorldPos(transform.translation.truncate()).chunk_slot(config);
+        if to_keep.contains(&slot) {
+            loaded.insert(slot, entity);
+        } else {
+            unload_chunk(entity, &mut chunk, &mut commands);
         }
     }
-}
 
-fn get_block(x: usize, y: usize, game_world: &GameWorld) -> Block {
-    if (y as f32) < game_world.get_height_in_blocks(x)
-        && (y + 1) as f32 >= game_world.get_height_in_blocks(x)
+    for slot in to_load
+        .into_iter()
+        .filter(|slot| !loaded.contains_key(slot))
     {
-        Block::Solid(SolidBlock::Surface)
-    } else if (y as f32) < game_world.get_height_in_blocks(x) {
-        Block::Solid(SolidBlock::Earth)
-    } else {
-        Block::Air
+        queue_chunk(
+            ChunkPos::from_slot(slot, config),
+            &game_world,
+            WorldPos::from_slot(slot, config),
+            &mut commands,
+            atlas_layout,
+            &rules,
+        );
     }
 }
 
-fn get_around_blocks(x: usize, y: usize, game_world: &GameWorld) -> [Block; 9] {
-    let up_x = x + 1;
-    let down_x = if x == 0 { 0 } else { x - 1 };
-    let up_y = y + 1;
-    let down_y = if y == 0 { 0 } else { y - 1 };
-
-    let up_left_block = get_block(down_x, up_y, game_world);
-    let up_block = get_block(x, up_y, game_world);
-    let up_right_block = get_block(up_x, up_y, game_world);
-    let left_block = get_block(down_x, y, game_world);
-    let center_block = get_block(x, y, game_world);
-    let right_block = get_block(up_x, y, game_world);
-    let down_left_block = get_block(down_x, down_y, game_world);
-    let down_block = get_block(x, down_y, game_world);
-    let down_right_block = get_block(up_x, down_y, game_world);
+fn on_chunk_edited(
+    trigger: Trigger<ChunkEditedEvent>,
+    mut query: Query<(Entity, &Transform, &mut Chunk)>,
+    mut commands: Commands,
+    game_world: Res<GameWorld>,
+    tiles: Res<Tiles>,
+    atlas_layout_handle: Res<TilesAtlasLayout>,
+    texture_atlases: Res<Assets<TextureAtlasLayout>>,
+    rules: Res<AutotileRules>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let atlas_layout = texture_atlases.get(&atlas_layout_handle.0).unwrap();
+    let chunk_index = trigger.event().chunk_index;
+    for (entity, transform, mut chunk) in query.iter_mut() {
+        if chunk.index != chunk_index {
+            continue;
+        }
+        //The old chunk goes away before the rebuilt one shows up, so observers see them in order
+        unload_chunk(entity, &mut chunk, &mut commands);
+        new_chunk(
+            chunk_index,
+            &game_world,
+            WorldPos(transform.translation.truncate()),
+            &mut commands,
+            &tiles,
+            atlas_layout,
+            &rules,
+            &mut meshes,
+        );
+    }
+}
 
+/// The block at the position and the eight around it, from up left to down right, row by row.
+/// Below the bottom row the bottom row is repeated.
+pub fn get_around_blocks(pos: BlockPos, game_world: &GameWorld) -> [Block; 9] {
+    let down = if pos.y == 0 { 0 } else { -1 };
     [
-        up_left_block,
-        up_block,
-        up_right_block,
-        left_block,
-        center_block,
-        right_block,
-        down_left_block,
-        down_block,
-        down_right_block,
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (-1, 0),
+        (0, 0),
+        (1, 0),
+        (-1, down),
+        (0, down),
+        (1, down),
     ]
+    .map(|(x, y)| game_world.get_block(pos.offset(x, y)))
 }
@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Character, CharacterRespawnEvent, CoinPouch, STARTING_COINS},
+    game::{restart_world, AppState, DayCount, FIRST_DAY},
+    seed::WorldSeed,
+    ui::TextFont,
+    HIGH_RES_LAYERS,
+};
+
+const BACKGROUND_COLOR: Color = Color::srgba(0., 0., 0., 0.7);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+const HOVERED_BUTTON_COLOR: Color = Color::srgb(0.35, 0.35, 0.45);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_systems(OnEnter(AppState::Menu), spawn_title_screen)
+            .add_systems(OnEnter(AppState::Paused), spawn_pause_screen)
+            .add_systems(OnEnter(AppState::GameOver), spawn_game_over_screen)
+            .add_systems(Update, (highlight_buttons, choose_action));
+    }
+}
+
+/// What a menu button does. Every button also answers to its key.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuAction {
+    Play,
+    Resume,
+    Respawn, //back at the spawn shrine, keeping the world and the coins
+    Restart, //a fresh world from the same seed
+    MainMenu,
+}
+
+impl MenuAction {
+    fn key(&self) -> KeyCode {
+        match self {
+            MenuAction::Play => KeyCode::Enter,
+            MenuAction::Resume => KeyCode::Escape,
+            MenuAction::Respawn => KeyCode::KeyR,
+            MenuAction::Restart => KeyCode::KeyN,
+            MenuAction::MainMenu => KeyCode::KeyM,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            MenuAction::Play => "Play (Enter)",
+            MenuAction::Resume => "Resume (Esc)",
+            MenuAction::Respawn => "Respawn (R)",
+            MenuAction::Restart => "Restart world (N)",
+            MenuAction::MainMenu => "Main menu (M)",
+        }
+    }
+}
+
+fn spawn_title_screen(
+    mut commands: Commands,
+    text_font: Res<TextFont>,
+    world_seed: Res<WorldSeed>,
+) {
+    let lines = [(format!("Seed: {}", world_seed.0), 36.0)];
+    spawn_screen(
+        &mut commands,
+        AppState::Menu,
+        &text_font.0,
+        &lines,
+        &[MenuAction::Play],
+    );
+}
+
+fn spawn_pause_screen(mut commands: Commands, text_font: Res<TextFont>) {
+    let lines = [("Paused".to_string(), 72.0)];
+    spawn_screen(
+        &mut commands,
+        AppState::Paused,
+        &text_font.0,
+        &lines,
+        &[MenuAction::Resume, MenuAction::MainMenu],
+    );
+}
+
+//Counts what the run added: the coins collected and the days gone by since the world began
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    text_font: Res<TextFont>,
+    coin_pouch_query: Query<&CoinPouch, With<Character>>,
+    day_count: Res<DayCount>,
+) {
+    let coins = coin_pouch_query
+        .get_single()
+        .map_or(0, |coin_pouch| coin_pouch.0.saturating_sub(STARTING_COINS));
+    let lines = [
+        ("Game over".to_string(), 72.0),
+        (format!("Coins collected: {}", coins), 36.0),
+        (format!("Days survived: {}", day_count.0 - FIRST_DAY), 36.0),
+    ];
+    spawn_screen(
+        &mut commands,
+        AppState::GameOver,
+        &text_font.0,
+        &lines,
+        &[
+            MenuAction::Respawn,
+            MenuAction::Restart,
+            MenuAction::MainMenu,
+        ],
+    );
+}
+
+//Lines of text over a column of buttons, gone when the game leaves `state`
+fn spawn_screen(
+    commands: &mut Commands,
+    state: AppState,
+    font: &Handle<Font>,
+    lines: &[(String, f32)],
+    actions: &[MenuAction],
+) {
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font_size,
+                font: font.clone(),
+                ..default()
+            },
+        )
+    };
+    commands
+        .spawn((
+            Name::new(format!("{:?} UI", state)),
+            StateScoped(state),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            HIGH_RES_LAYERS,
+        ))
+        .with_children(|parent| {
+            for (value, font_size) in lines {
+                parent.spawn(text(value, *font_size));
+            }
+            for action in actions {
+                parent
+                    .spawn((
+                        *action,
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(24.0), Val::Px(8.0)),
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(text(action.label(), 32.0));
+                    });
+            }
+        });
+}
+
+fn highlight_buttons(
+    mut query: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut background_color) in query.iter_mut() {
+        *background_color = match interaction {
+            Interaction::Hovered | Interaction::Pressed => HOVERED_BUTTON_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        }
+        .into();
+    }
+}
+
+//Clicking a button on screen or pressing its key
+fn choose_action(
+    button_query: Query<(Ref<Interaction>, &MenuAction)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    let Some(action) = button_query
+        .iter()
+        .find(|(interaction, action)| {
+            (interaction.is_changed() && **interaction == Interaction::Pressed)
+                || keys.just_pressed(action.key())
+        })
+        .map(|(_, action)| *action)
+    else {
+        return;
+    };
+    match action {
+        MenuAction::Respawn => commands.trigger(CharacterRespawnEvent),
+        MenuAction::Restart => commands.add(restart_world),
+        MenuAction::Play | MenuAction::Resume | MenuAction::MainMenu => {}
+    }
+    next_state.set(match action {
+        MenuAction::MainMenu => AppState::Menu,
+        _ => AppState::Playing,
+    });
+}
@@ -1,12 +1,16 @@
 use bevy::hierarchy::*;
 use bevy::prelude::*;
+use bevy_rand::prelude::*;
 use bevy_rapier2d::prelude::Collider;
-use rand::{distributions::Standard, prelude::*};
+use rand::prelude::*;
 
 use crate::{
+    coordinates::BlockPos,
+    game::{DespawnWorld, GameStartupSet, SpawnWorld},
     game_world::GameWorld,
+    loading::RequiredAssets,
     map::{Chunk, NewChunkEvent},
-    BLOCK_SIZE, CHUNK_WIDTH,
+    utils::rand_weighted_item,
 };
 
 pub struct PickablesPlugin;
@@ -16,7 +20,9 @@ impl Plugin for PickablesPlugin {
         app.observe(on_new_day)
             .observe(on_new_chunk)
             .observe(on_pickable_collected)
-            .add_systems(Startup, (load_textures, startup).chain());
+            .add_systems(Startup, load_textures)
+            .add_systems(SpawnWorld, startup.in_set(GameStartupSet::Pickables))
+            .add_systems(DespawnWorld, despawn_pickables);
     }
 }
 
@@ -26,7 +32,7 @@ struct TilesAtlasLayout(Handle<TextureAtlasLayout>);
 #[derive(Resource)]
 struct Tiles(Handle<Image>);
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum PickableItemType {
     Diammond,
     Gem,
@@ -54,21 +60,10 @@ impl PickableItemType {
     }
 }
 
-impl Distribution<PickableItemType> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PickableItemType {
-        match rng.gen_range(0..=3) {
-            0 => PickableItemType::Diammond,
-            1 => PickableItemType::Gem,
-            2 => PickableItemType::Emmerald,
-            _ => PickableItemType::Gold,
-        }
-    }
-}
-
 #[derive(Component)]
 pub struct Pickable {
     pub item_type: PickableItemType,
-    pub x: usize,
+    pub pos: BlockPos, //block the item lies in
 }
 
 #[derive(Component)]
@@ -85,22 +80,57 @@ pub struct PlacedPickableCollected {
 fn load_textures(
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     asset_server: Res<AssetServer>,
+    mut required_assets: ResMut<RequiredAssets>,
     mut commands: Commands,
 ) {
     let atlas_layout = TextureAtlasLayout::from_grid(UVec2::new(32, 32), 8, 5, None, None);
     commands.insert_resource(TilesAtlasLayout(texture_atlases.add(atlas_layout)));
-    commands.insert_resource(Tiles(asset_server.load("purple-valley-icon-set/icons.png")));
+    let texture = asset_server.load("purple-valley-icon-set/icons.png");
+    required_assets.track(texture.clone());
+    commands.insert_resource(Tiles(texture));
 }
 
-fn startup(mut commands: Commands, game_world: Res<GameWorld>) {
-    let mut rng = thread_rng();
+fn startup(
+    mut commands: Commands,
+    game_world: Res<GameWorld>,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+) {
+    let mut rng = global_entropy.fork_rng();
+    for pickable in generate_pickables(&game_world, &mut rng) {
+        commands.spawn(pickable);
+    }
+}
+
+//The placed ones are children of their chunk and go with it
+fn despawn_pickables(query: Query<Entity, With<Pickable>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Lays out every pickable of the world. The layout depends only on the world and the rng,
+/// so the same seed always places the same items at the same columns.
+/// Loose items lie on top of the ground, or of the structure standing there, and follow the
+/// weights of the biome at each column. The ones kept in structures come after them.
+pub fn generate_pickables<R: Rng + ?Sized>(game_world: &GameWorld, rng: &mut R) -> Vec<Pickable> {
     let pickables_count = rng.gen_range(8..64) as usize;
-    for _ in 0..pickables_count {
-        commands.spawn(Pickable {
-            item_type: random(),
-            x: game_world.get_random_x_block(),
+    let loose = (0..pickables_count).map(|_| {
+        let column = game_world.get_random_column(rng);
+        let weights = game_world.get_biome(column).pickable_weights();
+        Pickable {
+            item_type: rand_weighted_item(&weights, rng),
+            pos: game_world.top_solid_at(column).up(),
+        }
+    });
+    let in_structures = game_world
+        .structures()
+        .iter()
+        .flat_map(|structure| &structure.pickables)
+        .map(|(pos, item_type)| Pickable {
+            item_type: *item_type,
+            pos: *pos,
         });
-    }
+    loose.chain(in_structures).collect()
 }
 
 fn on_new_day(_: Trigger<NewChunkEvent>) {}
@@ -117,36 +147,34 @@ fn on_new_chunk(
     let event = trigger.event();
     let chunk_entity = event.chunk;
     let chunk = chunks.get(chunk_entity).unwrap();
-    let chunk_x_range = (chunk.index * CHUNK_WIDTH)..((chunk.index + 1) * CHUNK_WIDTH);
+    let config = &game_world.config;
+    let block_size = config.block_size;
     let items = pickables
         .iter()
-        .filter(|(_p, i)| chunk_x_range.contains(&i.x));
+        .filter(|(_p, i)| chunk.index.contains(i.pos, config));
     commands.entity(chunk_entity).with_children(|parent| {
         for (entity, item) in items {
             parent.spawn((
                 PlacedPickable {
                     entity,
-                    item_type: item.item_type.clone(),
+                    item_type: item.item_type,
                 },
                 SpriteBundle {
                     texture: tiles.0.clone(),
                     sprite: Sprite {
-                        custom_size: Some(Vec2::new(BLOCK_SIZE as f32, BLOCK_SIZE as f32)),
+                        custom_size: Some(Vec2::new(block_size as f32, block_size as f32)),
                         ..default()
                     },
-                    transform: Transform::from_translation(Vec3::new(
-                        ((item.x % (CHUNK_WIDTH)) * BLOCK_SIZE) as f32,
-                        game_world.get_surface(item.x) + (BLOCK_SIZE / 2) as f32,
-                        2.0,
-                    )),
+                    transform: Transform::from_translation(
+                        item.pos.local_position(config).extend(2.0),
+                    ),
                     ..default()
                 },
                 TextureAtlas {
                     layout: atlas_layout.0.clone(),
                     index: item.item_type.get_sprite_index(),
-                    ..default()
                 },
-                Collider::cuboid(BLOCK_SIZE as f32, BLOCK_SIZE as f32),
+                Collider::cuboid(block_size as f32, block_size as f32),
             ));
         }
     });
@@ -163,3 +191,40 @@ fn on_pickable_collected(
         println!("Pickable not found: {:?}", trigger.event().entity);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{seeded_pickables, test_world, world_for},
+        map::Block,
+        seed::WorldSeed,
+    };
+
+    fn layout(seed: WorldSeed) -> Vec<(BlockPos, PickableItemType)> {
+        seeded_pickables(&seed, &world_for(seed))
+            .into_iter()
+            .map(|pickable| (pickable.pos, pickable.item_type))
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_places_the_same_pickables() {
+        assert_eq!(layout(WorldSeed(42)), layout(WorldSeed(42)));
+    }
+
+    #[test]
+    fn another_seed_places_other_pickables() {
+        assert_ne!(layout(WorldSeed(42)), layout(WorldSeed(43)));
+    }
+
+    #[test]
+    fn loose_pickables_never_lie_inside_a_structure() {
+        let game_world = test_world();
+        //Every column a loose item can be dropped at, structures included
+        for x in 0..game_world.width {
+            let pos = game_world.top_solid_at(BlockPos::new(x, 0)).up();
+            assert_eq!(game_world.get_block(pos), Block::Air, "{:?}", pos);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::prelude::Resource;
+
+/// Seed that drives every random part of the world: terrain noise, entropy and spawning.
+/// The same seed always produces the same world.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    /// Reads the seed from `--seed <value>` on native or `?seed=<value>` on wasm.
+    /// Falls back to a random seed when none is given.
+    pub fn from_env() -> Self {
+        read_seed_argument()
+            .map(|value| WorldSeed::parse(&value))
+            .unwrap_or_else(|| WorldSeed(rand::random()))
+    }
+
+    /// Numeric values are used as they are, any other text is hashed,
+    /// so players can share seeds like `ducky`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.parse::<u64>() {
+            Ok(seed) => WorldSeed(seed),
+            Err(_) => WorldSeed(fnv1a(value.as_bytes())),
+        }
+    }
+
+    pub fn noise_seed(&self) -> u32 {
+        (self.0 ^ (self.0 >> 32)) as u32
+    }
+
+    pub fn entropy_seed(&self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+}
+
+//FNV-1a is used instead of DefaultHasher because its output is stable between Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_seed_argument() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_seed_argument() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seed="))
+        .map(|value| value.to_string())
+}
@@ -0,0 +1,348 @@
+use bevy::utils::HashMap;
+use bevy_rand::prelude::WyRand;
+use rand::prelude::*;
+
+use crate::{
+    biome::Biome,
+    config::WorldConfig,
+    coordinates::BlockPos,
+    enemy::SlimeKind,
+    game_world::GameWorld,
+    map::{Block, SolidBlock},
+    pickables::PickableItemType,
+    seed::WorldSeed,
+    utils::rand_weighted_item,
+};
+
+const STRUCTURE_SEED_SALT: u64 = 0xc2b2ae3d27d4eb4f; //keeps the structure stream apart from the others
+const MIN_SPACING: i32 = 24; //free columns between two structures
+const MAX_SURFACE_SLOPE: i32 = 1; //height difference a surface structure allows under its floor
+const PLACEMENT_TRIES: usize = 8; //random spots tried for each structure before giving up
+const VAULT_MIN_Y: i32 = 4; //lowest row of a vault, keeps it off the bedrock
+
+//Templates go from the top row down. ' ' keeps the terrain, '.' is air, '#' is stone,
+//'$' is air holding a pickable and 'e' is air an enemy spawns in.
+//Surface templates end with a foundation row that is sunk one row under the ground, so the
+//row above it is the floor and small dips under it leave no gaps.
+#[rustfmt::skip]
+const SPAWN_SHRINE: &[&str] = &[
+    "#.....#",
+    "#.....#",
+    "#######",
+    "#######",
+];
+#[rustfmt::skip]
+const RUIN: &[&str] = &[
+    "#........",
+    "#...#....",
+    "#.$.#..#.",
+    "#########",
+    "#########",
+];
+#[rustfmt::skip]
+const TOWER: &[&str] = &[
+    "#####",
+    "#.$.#",
+    "#...#",
+    "###.#",
+    "#....",
+    "#.e..",
+    "#####",
+    "#####",
+];
+#[rustfmt::skip]
+const VAULT: &[&str] = &[
+    "#########",
+    "#.......#",
+    "#.......#",
+    "#.$.e.$.#",
+    "#########",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StructureKind {
+    SpawnShrine,
+    Ruin,
+    Tower,
+    Vault,
+}
+
+impl StructureKind {
+    //Kinds scattered around the world, with the world columns there are per structure of the
+    //kind. The shrine is placed on its own
+    const SCATTERED: [(StructureKind, i32); 3] = [
+        (StructureKind::Vault, 120),
+        (StructureKind::Ruin, 160),
+        (StructureKind::Tower, 240),
+    ];
+
+    fn template(&self) -> &'static [&'static str] {
+        match self {
+            StructureKind::SpawnShrine => SPAWN_SHRINE,
+            StructureKind::Ruin => RUIN,
+            StructureKind::Tower => TOWER,
+            StructureKind::Vault => VAULT,
+        }
+    }
+
+    fn width(&self) -> i32 {
+        self.template()[0].len() as i32
+    }
+
+    fn height(&self) -> i32 {
+        self.template().len() as i32
+    }
+
+    fn is_underground(&self) -> bool {
+        matches!(self, StructureKind::Vault)
+    }
+
+    fn fits_biome(&self, biome: Biome) -> bool {
+        match self {
+            StructureKind::Ruin => matches!(biome, Biome::Meadow | Biome::Desert),
+            StructureKind::Tower => matches!(biome, Biome::Snow | Biome::Meadow),
+            StructureKind::SpawnShrine | StructureKind::Vault => true,
+        }
+    }
+}
+
+/// A template stamped into the world, with what it holds.
+#[derive(Clone, Debug)]
+pub struct Structure {
+    pub kind: StructureKind,
+    pub origin: BlockPos, //bottom left block of the template
+    pub pickables: Vec<(BlockPos, PickableItemType)>,
+    pub enemies: Vec<(BlockPos, SlimeKind)>,
+}
+
+impl Structure {
+    //Picks what the template's markers hold from the biome the structure stands in
+    fn new<R: Rng + ?Sized>(
+        kind: StructureKind,
+        origin: BlockPos,
+        biome: Biome,
+        config: &WorldConfig,
+        rng: &mut R,
+    ) -> Self {
+        let mut structure = Structure {
+            kind,
+            origin,
+            pickables: Vec::new(),
+            enemies: Vec::new(),
+        };
+        for (pos, cell) in template_cells(kind.template()) {
+            let pos = origin.offset(pos.x, pos.y).wrap(config);
+            match cell {
+                '$' => {
+                    let item_type = rand_weighted_item(&biome.pickable_weights(), rng);
+                    structure.pickables.push((pos, item_type));
+                }
+                'e' => {
+                    let kind = rand_weighted_item(biome.enemy_spawn_table(), rng);
+                    structure.enemies.push((pos, kind));
+                }
+                _ => {}
+            }
+        }
+        structure
+    }
+
+    /// Blocks the template forces, air included.
+    pub fn blocks<'a>(
+        &'a self,
+        config: &'a WorldConfig,
+    ) -> impl Iterator<Item = (BlockPos, Block)> + 'a {
+        template_cells(self.kind.template()).filter_map(move |(pos, cell)| {
+            let block = match cell {
+                '#' => Block::Solid(SolidBlock::Stone),
+                '.' | '$' | 'e' => Block::Air,
+                _ => return None,
+            };
+            Some((self.origin.offset(pos.x, pos.y).wrap(config), block))
+        })
+    }
+}
+
+//Cells of a template with their offset from its bottom left corner
+fn template_cells(template: &'static [&'static str]) -> impl Iterator<Item = (BlockPos, char)> {
+    let height = template.len() as i32;
+    template.iter().enumerate().flat_map(move |(row, line)| {
+        line.chars()
+            .enumerate()
+            .map(move |(col, cell)| (BlockPos::new(col as i32, height - 1 - row as i32), cell))
+    })
+}
+
+/// Places the spawn shrine under the spawn point, then scatters the other structures at seeded
+/// spots where the terrain fits them and they keep their distance from each other.
+/// Only reads the generated terrain, so the same seed always gives the same structures.
+pub fn place_structures(seed: &WorldSeed, game_world: &GameWorld) -> Vec<Structure> {
+    let config = &game_world.config;
+    let mut rng = WyRand::seed_from_u64(seed.0 ^ STRUCTURE_SEED_SALT);
+
+    let spawn_column = game_world.spawn_point();
+    let shrine = StructureKind::SpawnShrine;
+    let shrine_origin = game_world
+        .surface_at(spawn_column)
+        .offset(-shrine.width() / 2, -1)
+        .wrap(config);
+    let mut structures = vec![Structure::new(
+        shrine,
+        shrine_origin,
+        game_world.get_biome(spawn_column),
+        config,
+        &mut rng,
+    )];
+
+    for (kind, columns_per_structure) in StructureKind::SCATTERED {
+        for _ in 0..(game_world.width / columns_per_structure).max(1) {
+            let origin = (0..PLACEMENT_TRIES)
+                .filter_map(|_| pick_origin(kind, game_world, &mut rng))
+                .find(|origin| {
+                    fits_terrain(kind, *origin, game_world)
+                        && structures
+                            .iter()
+                            .all(|other| is_apart(kind, *origin, other.kind, other.origin, config))
+                });
+            if let Some(origin) = origin {
+                let biome = game_world.get_biome(origin);
+                structures.push(Structure::new(kind, origin, biome, config, &mut rng));
+            }
+        }
+    }
+    structures
+}
+
+//Surface structures sink their foundation one row under the ground at a random column,
+//underground ones go at a random row under the stone line
+fn pick_origin<R: Rng + ?Sized>(
+    kind: StructureKind,
+    game_world: &GameWorld,
+    rng: &mut R,
+) -> Option<BlockPos> {
+    let column = game_world.get_random_column(rng);
+    if !kind.is_underground() {
+        return Some(game_world.surface_at(column).offset(0, -1));
+    }
+    let top = game_world.surface_at(column).y - kind.height();
+    if top <= VAULT_MIN_Y {
+        return None;
+    }
+    Some(BlockPos::new(column.x, rng.gen_range(VAULT_MIN_Y..top)))
+}
+
+fn fits_terrain(kind: StructureKind, origin: BlockPos, game_world: &GameWorld) -> bool {
+    let config = &game_world.config;
+    if !origin.offset(0, kind.height() - 1).is_inside(config) || origin.y < 0 {
+        return false;
+    }
+    if kind.is_underground() {
+        //Walled in by stone all around, caves aside
+        return (0..kind.width())
+            .all(|x| (0..kind.height()).all(|y| game_world.is_stone(origin.offset(x, y))));
+    }
+    let floor = origin.y + 1;
+    kind.fits_biome(game_world.get_biome(origin))
+        && (0..kind.width()).all(|x| {
+            let surface = game_world.surface_at(origin.offset(x, 0)).y;
+            (surface - floor).abs() <= MAX_SURFACE_SLOPE
+        })
+}
+
+//Whether the two footprints have at least `MIN_SPACING` free columns between them on both
+//sides, around the world
+fn is_apart(
+    kind: StructureKind,
+    origin: BlockPos,
+    other_kind: StructureKind,
+    other_origin: BlockPos,
+    config: &WorldConfig,
+) -> bool {
+    let world_width = config.world_width() as i32;
+    let distance = (other_origin.x - origin.x).rem_euclid(world_width);
+    distance >= kind.width() + MIN_SPACING
+        && distance + other_kind.width() + MIN_SPACING <= world_width
+}
+
+/// Stamped blocks of every structure, keyed by wrapped block positions.
+pub fn stamp_structures(
+    structures: &[Structure],
+    config: &WorldConfig,
+) -> HashMap<BlockPos, Block> {
+    structures
+        .iter()
+        .flat_map(|structure| structure.blocks(config))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::test_world;
+
+    #[test]
+    fn the_spawn_shrine_stands_under_the_spawn_point() {
+        let game_world = test_world();
+        let shrine = &game_world.structures()[0];
+        assert_eq!(shrine.kind, StructureKind::SpawnShrine);
+        let spawn_point = game_world.spawn_point();
+        let column = (spawn_point.x - shrine.origin.x).rem_euclid(game_world.width);
+        assert!(column < shrine.kind.width());
+        assert!(spawn_point.y >= shrine.origin.y + shrine.kind.height());
+    }
+
+    #[test]
+    fn structures_keep_their_distance() {
+        let game_world = test_world();
+        let config = &game_world.config;
+        let structures = game_world.structures();
+        assert!(structures.len() > 1);
+        for (index, structure) in structures.iter().enumerate() {
+            for other in &structures[index + 1..] {
+                let columns = |s: &Structure| {
+                    (-MIN_SPACING..s.kind.width() + MIN_SPACING)
+                        .map(|x| s.origin.offset(x, 0).wrap(config).x)
+                        .collect::<Vec<_>>()
+                };
+                let other_columns = (0..other.kind.width())
+                    .map(|x| other.origin.offset(x, 0).wrap(config).x)
+                    .collect::<Vec<_>>();
+                assert!(
+                    !columns(structure).iter().any(|x| other_columns.contains(x)),
+                    "{:?} too close to {:?}",
+                    structure,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn templates_are_stamped_into_the_world() {
+        let game_world = test_world();
+        let config = &game_world.config;
+        for structure in game_world.structures() {
+            for (pos, block) in structure.blocks(config) {
+                assert_eq!(game_world.is_solid(pos), block != Block::Air, "{:?}", pos);
+            }
+            for (pos, _) in &structure.pickables {
+                assert_eq!(game_world.get_block(*pos), Block::Air);
+            }
+            for (pos, _) in &structure.enemies {
+                assert_eq!(game_world.get_block(*pos), Block::Air);
+            }
+        }
+    }
+
+    #[test]
+    fn the_same_seed_places_the_same_structures() {
+        let origins = |game_world: GameWorld| {
+            game_world
+                .structures()
+                .iter()
+                .map(|structure| (structure.kind, structure.origin))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(origins(test_world()), origins(test_world()));
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::{
+    biome::Biome,
+    character::Character,
+    coordinates::{BlockPos, WorldPos},
+    game::AppState,
+    game_world::GameWorld,
+};
+
+const THEME_TOGGLE_KEY: KeyCode = KeyCode::KeyT;
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Theme>()
+            .init_resource::<ThemeMode>()
+            .add_systems(
+                Update,
+                (toggle_theme_mode, follow_biome)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Look of the terrain, background and HUD. Every module keeps both variants of its textures
+/// and swaps them when this resource changes, so nothing has to be rebuilt.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Standard,
+    White,
+}
+
+/// How the theme is picked: `Auto` follows the biome under the character.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThemeMode {
+    #[default]
+    Auto,
+    Fixed(Theme),
+}
+
+/// Both variants of a themed texture.
+#[derive(Clone)]
+pub struct ThemedImage {
+    pub standard: Handle<Image>,
+    pub white: Handle<Image>,
+}
+
+impl ThemedImage {
+    /// Loads `<path>.png` and its `<path>WhiteVer.png` counterpart.
+    pub fn load(asset_server: &AssetServer, path: &str) -> Self {
+        ThemedImage {
+            standard: asset_server.load(format!("{}.png", path)),
+            white: asset_server.load(format!("{}WhiteVer.png", path)),
+        }
+    }
+
+    pub fn get(&self, theme: Theme) -> Handle<Image> {
+        match theme {
+            Theme::Standard => self.standard.clone(),
+            Theme::White => self.white.clone(),
+        }
+    }
+}
+
+fn toggle_theme_mode(keys: Res<ButtonInput<KeyCode>>, mut mode: ResMut<ThemeMode>) {
+    if !keys.just_pressed(THEME_TOGGLE_KEY) {
+        return;
+    }
+    *mode = match *mode {
+        ThemeMode::Auto => ThemeMode::Fixed(Theme::Standard),
+        ThemeMode::Fixed(Theme::Standard) => ThemeMode::Fixed(Theme::White),
+        ThemeMode::Fixed(Theme::White) => ThemeMode::Auto,
+    };
+}
+
+fn follow_biome(
+    mode: Res<ThemeMode>,
+    mut theme: ResMut<Theme>,
+    char_query: Query<&Transform, With<Character>>,
+    game_world: Res<GameWorld>,
+) {
+    let new_theme = match *mode {
+        ThemeMode::Fixed(fixed) => fixed,
+        ThemeMode::Auto => {
+            let Ok(char_transform) = char_query.get_single() else {
+                return;
+            };
+            let position = WorldPos(char_transform.translation.truncate());
+            match game_world.get_biome(BlockPos::from_world(position, &game_world.config)) {
+                Biome::Snow => Theme::White,
+                _ => Theme::Standard,
+            }
+        }
+    };
+    //Only write on change, the swapping systems run on `resource_changed`
+    theme.set_if_neq(new_theme);
+}
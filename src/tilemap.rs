@@ -0,0 +1,174 @@
+use bevy::{
+    color::{Color, ColorToComponents},
+    math::{Rect, Vec2},
+    render::{
+        mesh::{Indices, Mesh, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::TextureAtlasLayout,
+};
+
+use crate::{
+    autotile::AutotileRules,
+    biome::TilesetVariant,
+    coordinates::ChunkPos,
+    game_world::GameWorld,
+    map::{get_around_blocks, Block, SolidBlock},
+    pickables::PickableItemType,
+};
+
+/// Every block of a chunk batched into one mesh per texture, instead of one sprite per block.
+/// Props get their own meshes so they can be drawn behind the terrain.
+pub struct ChunkMeshes {
+    pub standard: Option<Mesh>,
+    pub white: Option<Mesh>,
+    pub colors: Option<Mesh>,
+    pub standard_props: Option<Mesh>,
+    pub white_props: Option<Mesh>,
+}
+
+#[derive(Default)]
+struct TileMeshBuilder {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl TileMeshBuilder {
+    fn push_quad(&mut self, center: Vec2, size: f32, uv: Rect, color: Color) {
+        let start = self.positions.len() as u32;
+        let half = size / 2.;
+        self.positions.extend([
+            [center.x - half, center.y - half, 0.],
+            [center.x + half, center.y - half, 0.],
+            [center.x + half, center.y + half, 0.],
+            [center.x - half, center.y + half, 0.],
+        ]);
+        //Texture coordinates grow downwards
+        self.uvs.extend([
+            [uv.min.x, uv.max.y],
+            [uv.max.x, uv.max.y],
+            [uv.max.x, uv.min.y],
+            [uv.min.x, uv.min.y],
+        ]);
+        self.colors.extend([color.to_linear().to_f32_array(); 4]);
+        self.indices
+            .extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    fn build(self) -> Option<Mesh> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        Some(
+            Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::RENDER_WORLD,
+            )
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, self.colors)
+            .with_inserted_indices(Indices::U32(self.indices)),
+        )
+    }
+}
+
+pub fn build_chunk_meshes(
+    chunk_index: ChunkPos,
+    game_world: &GameWorld,
+    atlas_layout: &TextureAtlasLayout,
+    rules: &AutotileRules,
+) -> ChunkMeshes {
+    let config = &game_world.config;
+    let block_size = config.block_size as f32;
+    let first_block = chunk_index.first_block(config);
+    let mut standard = TileMeshBuilder::default();
+    let mut white = TileMeshBuilder::default();
+    let mut colors = TileMeshBuilder::default();
+    let mut standard_props = TileMeshBuilder::default();
+    let mut white_props = TileMeshBuilder::default();
+
+    for col_x in 0..config.chunk_width as i32 {
+        for y in 0..config.world_height as i32 {
+            let pos = first_block.offset(col_x, y);
+            let Block::Solid(block) = game_world.get_block(pos) else {
+                continue;
+            };
+            let position = pos.local_position(config);
+
+            match block {
+                SolidBlock::Stone | SolidBlock::Ore(_) => {
+                    colors.push_quad(position, block_size, Rect::default(), block_color(block));
+                }
+                SolidBlock::Earth | SolidBlock::Surface(_) => {
+                    let around_blocks = get_around_blocks(pos, game_world);
+                    let (variant, index, tint) = get_tile(block, &around_blocks, rules);
+                    let builder = match variant {
+                        TilesetVariant::Standard => &mut standard,
+                        TilesetVariant::White => &mut white,
+                    };
+                    builder.push_quad(position, block_size, get_tile_uv(atlas_layout, index), tint);
+
+                    //Props stand on the air above the surface and go away with the block
+                    let SolidBlock::Surface(_) = block else {
+                        continue;
+                    };
+                    if around_blocks[1] != Block::Air || !pos.up().is_inside(config) {
+                        continue;
+                    }
+                    let Some(prop) = game_world.get_prop(pos) else {
+                        continue;
+                    };
+                    let props = match variant {
+                        TilesetVariant::Standard => &mut standard_props,
+                        TilesetVariant::White => &mut white_props,
+                    };
+                    props.push_quad(
+                        pos.up().local_position(config),
+                        block_size,
+                        get_tile_uv(atlas_layout, prop),
+                        tint,
+                    );
+                }
+            }
+        }
+    }
+
+    ChunkMeshes {
+        standard: standard.build(),
+        white: white.build(),
+        colors: colors.build(),
+        standard_props: standard_props.build(),
+        white_props: white_props.build(),
+    }
+}
+
+/// Tileset, atlas index and tint of a textured block, picked from the blocks around it.
+pub fn get_tile(
+    block: SolidBlock,
+    around_blocks: &[Block; 9],
+    rules: &AutotileRules,
+) -> (TilesetVariant, usize, Color) {
+    let index = rules.get_index(block, around_blocks);
+    match block {
+        SolidBlock::Surface(biome) => (biome.tileset(), index, biome.surface_tint()),
+        _ => (TilesetVariant::Standard, index, Color::WHITE),
+    }
+}
+
+pub fn block_color(block: SolidBlock) -> Color {
+    match block {
+        SolidBlock::Ore(PickableItemType::Diammond) => Color::linear_rgb(0.4, 0.9, 1.0),
+        SolidBlock::Ore(PickableItemType::Gem) => Color::linear_rgb(0.9, 0.3, 0.8),
+        SolidBlock::Ore(PickableItemType::Emmerald) => Color::linear_rgb(0.2, 0.8, 0.3),
+        SolidBlock::Ore(PickableItemType::Gold) => Color::linear_rgb(1.0, 0.8, 0.2),
+        _ => Color::linear_rgb(0.5, 0.5, 0.5),
+    }
+}
+
+fn get_tile_uv(atlas_layout: &TextureAtlasLayout, index: usize) -> Rect {
+    let rect = atlas_layout.textures[index].as_rect();
+    let size = atlas_layout.size.as_vec2();
+    Rect::from_corners(rect.min / size, rect.max / size)
+}
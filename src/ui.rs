@@ -4,7 +4,12 @@ use bevy::{
 };
 
 use crate::{
-    character::{Character, CoinPouch, HealthPoints},
+    character::{Character, CoinPouch},
+    game::InGame,
+    health::HealthPoints,
+    loading::RequiredAssets,
+    seed::WorldSeed,
+    theme::{Theme, ThemedImage},
     HIGH_RES_LAYERS,
 };
 
@@ -12,8 +17,13 @@ pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, (load_assets, startup).chain())
-            .add_systems(FixedUpdate, (update_coins, update_health_points));
+        app.add_systems(Startup, load_assets)
+            .add_systems(OnEnter(InGame), startup)
+            .add_systems(
+                FixedUpdate,
+                (update_coins, update_health_points).run_if(in_state(InGame)),
+            )
+            .add_systems(Update, apply_theme.run_if(resource_changed::<Theme>));
     }
 }
 
@@ -29,11 +39,19 @@ struct HealthPointsNodeUI;
 #[derive(Component)]
 struct HealthPointIconUI;
 
+#[derive(Component)]
+struct WorldSeedTextUI;
+
+/// Font every screen of the UI is written with.
 #[derive(Resource)]
-struct TextFont(Handle<Font>);
+pub struct TextFont(pub Handle<Font>);
 
 #[derive(Resource)]
-struct HeartsAndCoinsTexture(Handle<Image>);
+struct HeartsAndCoinsTexture(ThemedImage);
+
+//HUD image drawn from the hearts and coins texture
+#[derive(Component)]
+struct HeartsAndCoinsImageUI;
 
 #[derive(Resource)]
 struct HeartsAndCoinsTextureAtlas(Handle<TextureAtlasLayout>);
@@ -42,13 +60,18 @@ fn load_assets(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut required_assets: ResMut<RequiredAssets>,
 ) {
     let font_handle: Handle<Font> = asset_server.load("fonts/courneuf-family/Courneuf-Regular.ttf");
+    required_assets.track(font_handle.clone());
     commands.insert_resource(TextFont(font_handle));
 
-    let texture_handle: Handle<Image> =
-        asset_server.load("bgp_catdev/player_and_ui/Basic_HeartsAndCoins.png");
-    commands.insert_resource(HeartsAndCoinsTexture(texture_handle));
+    let texture = ThemedImage::load(
+        &asset_server,
+        "bgp_catdev/player_and_ui/Basic_HeartsAndCoins",
+    );
+    required_assets.track_themed(&texture);
+    commands.insert_resource(HeartsAndCoinsTexture(texture));
 
     let texture_atlas = TextureAtlasLayout::from_grid(UVec2::splat(16), 5, 2, None, None);
     let texture_atlas_handle: Handle<TextureAtlasLayout> = texture_atlases.add(texture_atlas);
@@ -60,10 +83,13 @@ fn startup(
     text_font_handle: Res<TextFont>,
     texture_handle: Res<HeartsAndCoinsTexture>,
     texture_atlas_handle: Res<HeartsAndCoinsTextureAtlas>,
+    world_seed: Res<WorldSeed>,
+    theme: Res<Theme>,
 ) {
     commands
         .spawn((
             Name::new("Main UI node"),
+            StateScoped(InGame),
             NodeBundle {
                 style: Style {
                     width: Val::Percent(100.0),
@@ -107,13 +133,14 @@ fn startup(
                 ))
                 .with_children(|parent| {
                     parent.spawn((
+                        HeartsAndCoinsImageUI,
                         ImageBundle {
                             style: Style {
                                 width: Val::Px(64.),
                                 height: Val::Px(64.),
                                 ..default()
                             },
-                            image: UiImage::new(texture_handle.0.clone()),
+                            image: UiImage::new(texture_handle.0.get(*theme)),
                             ..default()
                         },
                         TextureAtlas {
@@ -134,6 +161,19 @@ fn startup(
                         )]),
                     ));
                 });
+
+            parent.spawn((
+                Name::new("World seed UI"),
+                WorldSeedTextUI,
+                TextBundle::from_sections([TextSection::new(
+                    format!("Seed: {}", world_seed.0),
+                    TextStyle {
+                        font_size: 24.0,
+                        font: text_font_handle.0.clone(),
+                        ..default()
+                    },
+                )]),
+            ));
         });
 }
 
@@ -161,6 +201,7 @@ fn update_health_points(
     mut health_points_ui_query: Query<(Entity, &mut Style), With<HealthPointsNodeUI>>,
     texture_handle: Res<HeartsAndCoinsTexture>,
     texture_atlas_handle: Res<HeartsAndCoinsTextureAtlas>,
+    theme: Res<Theme>,
 ) {
     let (health_points_ui_entity, mut health_points_style) = health_points_ui_query.single_mut();
 
@@ -183,13 +224,14 @@ fn update_health_points(
                 let heart_point_entity = commands
                     .spawn((
                         HealthPointIconUI,
+                        HeartsAndCoinsImageUI,
                         ImageBundle {
                             style: Style {
                                 width: Val::Px(64.),
                                 height: Val::Px(64.),
                                 ..default()
                             },
-                            image: UiImage::new(texture_handle.0.clone()),
+                            image: UiImage::new(texture_handle.0.get(*theme)),
                             ..default()
                         },
                         TextureAtlas {
@@ -210,3 +252,13 @@ fn update_health_points(
         }
     };
 }
+
+fn apply_theme(
+    theme: Res<Theme>,
+    texture_handle: Res<HeartsAndCoinsTexture>,
+    mut images_query: Query<&mut UiImage, With<HeartsAndCoinsImageUI>>,
+) {
+    for mut image in images_query.iter_mut() {
+        image.texture = texture_handle.0.get(*theme);
+    }
+}
@@ -1,5 +1,5 @@
 use noise::utils::{NoiseImage, NoiseMap};
-use rand::Rng;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
 
 #[allow(dead_code)]
 pub fn write_example_to_file(map: &NoiseMap, filename: &str) {
@@ -26,8 +26,13 @@ pub fn write_image_to_file(image: &NoiseImage, filename: &str) {
 }
 
 #[allow(dead_code)]
-pub fn rand_list_item<T: Copy>(list: &[T]) -> T {
-    let mut rng = rand::thread_rng();
+pub fn rand_list_item<T: Copy, R: Rng + ?Sized>(list: &[T], rng: &mut R) -> T {
     let index = rng.gen_range(0..list.len());
     list[index]
 }
+
+pub fn rand_weighted_item<T: Copy, R: Rng + ?Sized>(list: &[(T, u32)], rng: &mut R) -> T {
+    let weights = WeightedIndex::new(list.iter().map(|(_, weight)| *weight))
+        .expect("Weighted list must have a positive total weight.");
+    list[weights.sample(rng)].0
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::PhysicsSet;
+
+use crate::{character::Character, config::WorldConfig, game::InGame, game_world::GameWorld};
+
+pub struct WrapAroundPlugin;
+
+impl Plugin for WrapAroundPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        //Before rapier reads the transforms, so bodies and colliders move in the same frame
+        app.configure_sets(PostUpdate, WorldRebaseSet.before(PhysicsSet::SyncBackend))
+            .add_systems(
+                PostUpdate,
+                rebase_world.in_set(WorldRebaseSet).run_if(in_state(InGame)),
+            );
+    }
+}
+
+/// Entities placed in the game world. When the character crosses the world seam they are all
+/// moved a whole turn around the world together, so positions stay close to the origin on every
+/// lap. Children follow their parent, so only top level entities need it.
+#[derive(Component)]
+pub struct WorldWrap;
+
+/// Triggered after every `WorldWrap` entity was moved `offset` pixels along x, for anything
+/// that keeps world positions outside of a transform.
+#[derive(Event)]
+pub struct WorldRebasedEvent {
+    pub offset: f32,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorldRebaseSet;
+
+fn rebase_world(
+    mut query: Query<(&mut Transform, Has<Character>), With<WorldWrap>>,
+    game_world: Res<GameWorld>,
+    mut commands: Commands,
+) {
+    let Some(character_x) = query
+        .iter()
+        .find(|(_, is_character)| *is_character)
+        .map(|(transform, _)| transform.translation.x)
+    else {
+        return;
+    };
+    let offset = rebase_offset(character_x, &game_world.config);
+    if offset == 0. {
+        return;
+    }
+    for (mut transform, _) in query.iter_mut() {
+        transform.translation.x += offset;
+    }
+    commands.trigger(WorldRebasedEvent { offset });
+}
+
+//The seam sits half a turn away from the origin on both sides
+fn rebase_offset(x: f32, config: &WorldConfig) -> f32 {
+    let turn = config.world_width_in_pixels() as f32;
+    if x > turn / 2. {
+        -turn
+    } else if x < -turn / 2. {
+        turn
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinates::{BlockPos, ChunkPos, WorldPos};
+
+    #[test]
+    fn only_positions_past_the_seam_are_rebased() {
+        let config = WorldConfig::default();
+        let half_turn = config.world_width_in_pixels() as f32 / 2.;
+        assert_eq!(rebase_offset(0., &config), 0.);
+        assert_eq!(rebase_offset(half_turn, &config), 0.);
+        assert_eq!(rebase_offset(-half_turn, &config), 0.);
+        assert_eq!(rebase_offset(half_turn + 1., &config), -2. * half_turn);
+        assert_eq!(rebase_offset(-half_turn - 1., &config), 2. * half_turn);
+    }
+
+    #[test]
+    fn rebased_positions_stay_on_the_same_block_and_chunk() {
+        let config = WorldConfig::default();
+        let turn = config.world_width_in_pixels() as f32;
+        for step in -400..400 {
+            let position = WorldPos(Vec2::new(step as f32 * 40.25, 0.));
+            let offset = rebase_offset(position.0.x, &config);
+            let rebased = WorldPos(position.0 + Vec2::new(offset, 0.));
+            assert!(rebased.0.x.abs() <= turn / 2.);
+            assert_eq!(
+                BlockPos::from_world(rebased, &config),
+                BlockPos::from_world(position, &config)
+            );
+            assert_eq!(
+                ChunkPos::from_slot(rebased.chunk_slot(&config), &config),
+                ChunkPos::from_slot(position.chunk_slot(&config), &config)
+            );
+        }
+    }
+}